@@ -18,35 +18,205 @@ use std::sync::OnceLock;
 static ATOM_COLORS: OnceLock<Vec<Color>> = OnceLock::new();
 // 3c4dc50f ends here
 
+// [[file:../bevy.note::9e61b0d4][9e61b0d4]]
+use std::collections::HashMap;
+
+/// Mesh and material handles shared by all displayed atoms and bonds, so that
+/// the number of assets does not grow with the number of atoms or frames.
+#[derive(Resource, Debug, Default)]
+pub struct AssetCache {
+    /// Sphere meshes of atoms keyed by element number and radius
+    spheres: HashMap<(usize, u32), Handle<Mesh>>,
+    /// Unit-height cylinder meshes of bonds keyed by radius
+    cylinders: HashMap<u32, Handle<Mesh>>,
+    /// Materials keyed by color
+    materials: HashMap<u32, Handle<StandardMaterial>>,
+    /// Translucent materials keyed by color
+    translucent: HashMap<u32, Handle<StandardMaterial>>,
+    /// Selection highlights keyed by color of the base material
+    highlights: HashMap<u32, Highlight<StandardMaterial>>,
+}
+
+impl AssetCache {
+    /// Return the shared sphere mesh for atoms of `element` in `radius`.
+    pub fn sphere(&mut self, element: usize, radius: f32, meshes: &mut Assets<Mesh>) -> Handle<Mesh> {
+        self.spheres
+            .entry((element, radius.to_bits()))
+            .or_insert_with(|| {
+                meshes.add(Mesh::from(shape::UVSphere {
+                    radius,
+                    ..default()
+                }))
+            })
+            .clone()
+    }
+
+    /// Return the shared cylinder mesh in `radius`. The cylinder has unit
+    /// height, and should be scaled to bond length with its transform.
+    pub fn cylinder(&mut self, radius: f32, meshes: &mut Assets<Mesh>) -> Handle<Mesh> {
+        self.cylinders
+            .entry(radius.to_bits())
+            .or_insert_with(|| {
+                meshes.add(Mesh::from(shape::Cylinder {
+                    radius,
+                    height: 1.0,
+                    ..default()
+                }))
+            })
+            .clone()
+    }
+
+    /// Return the shared material in `color`.
+    pub fn material(&mut self, color: Color, materials: &mut Assets<StandardMaterial>) -> Handle<StandardMaterial> {
+        self.materials
+            .entry(color.as_rgba_u32())
+            .or_insert_with(|| materials.add(color.into()))
+            .clone()
+    }
+
+    /// Return selection highlight for material in `color`. The tinted
+    /// materials of `HIGHLIGHT_TINT` are created once and shared, instead of
+    /// being created for each atom.
+    pub fn highlight(&mut self, color: Color, materials: &mut Assets<StandardMaterial>) -> Highlight<StandardMaterial> {
+        self.highlights
+            .entry(color.as_rgba_u32())
+            .or_insert_with(|| {
+                let base: StandardMaterial = color.into();
+                let mut tint = |kind: &Option<HighlightKind<StandardMaterial>>| match kind {
+                    Some(HighlightKind::Dynamic { function, .. }) => Some(HighlightKind::Fixed(materials.add(function(&base)))),
+                    other => other.clone(),
+                };
+                Highlight {
+                    hovered: tint(&HIGHLIGHT_TINT.hovered),
+                    pressed: tint(&HIGHLIGHT_TINT.pressed),
+                    selected: tint(&HIGHLIGHT_TINT.selected),
+                }
+            })
+            .clone()
+    }
+
+    /// Return the shared translucent material in `color`, visible from both sides.
+    pub fn translucent(&mut self, color: Color, materials: &mut Assets<StandardMaterial>) -> Handle<StandardMaterial> {
+        let color = color.with_a(0.5);
+        self.translucent
+            .entry(color.as_rgba_u32())
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    alpha_mode: AlphaMode::Blend,
+                    double_sided: true,
+                    cull_mode: None,
+                    ..default()
+                })
+            })
+            .clone()
+    }
+
+    /// Return the shared material for selected objects in `color`.
+    pub fn selected_material(&mut self, color: Color, materials: &mut Assets<StandardMaterial>) -> Handle<StandardMaterial> {
+        match self.highlight(color, materials).selected {
+            Some(HighlightKind::Fixed(handle)) => handle,
+            _ => self.material(color, materials),
+        }
+    }
+}
+
+/// Assets required for spawning molecule entities
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct MoleculeAssets<'w> {
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub materials: ResMut<'w, Assets<StandardMaterial>>,
+    pub cache: ResMut<'w, AssetCache>,
+}
+// 9e61b0d4 ends here
+
 // [[file:../bevy.note::14379cd1][14379cd1]]
+/// Create line segment from `pi` to `pj` as cylinder in `radius`, with
+/// shared mesh and material taken from `assets`.
 fn create_line_segment(
     pi: Vec3,
     pj: Vec3,
     visible: bool,
-    mut meshes: &mut ResMut<Assets<Mesh>>,
-    mut materials: &mut ResMut<Assets<StandardMaterial>>,
     radius: f32,
     color: Color,
+    assets: &mut (&mut Assets<Mesh>, &mut Assets<StandardMaterial>, &mut AssetCache),
 ) -> PbrBundle {
+    let (meshes, materials, cache) = assets;
+    PbrBundle {
+        mesh: cache.cylinder(radius, meshes),
+        visibility: visibility(visible),
+        material: cache.material(color, materials),
+        transform: line_segment_transform(pi, pj),
+        ..default()
+    }
+}
+
+/// Return the transform of unit cylinder for line segment from `pi` to `pj`.
+fn line_segment_transform(pi: Vec3, pj: Vec3) -> Transform {
     let center = (pi + pj) / 2.0;
     let dij = pj - pi;
     let lij = dij.length();
     let rot = Quat::from_rotation_arc(Vec3::Y, dij.normalize());
-    let transform = Transform::from_translation(center).with_rotation(rot);
+    // the shared cylinder mesh has unit height
+    Transform::from_translation(center)
+        .with_rotation(rot)
+        .with_scale(Vec3::new(1.0, lij, 1.0))
+}
+// 14379cd1 ends here
 
-    PbrBundle {
-        mesh: meshes.add(Mesh::from(shape::Cylinder {
-            radius,
-            height: lij,
-            ..default()
-        })),
-        visibility: visibility(visible),
-        material: materials.add(color.into()),
-        transform,
-        ..default()
+// [[file:../bevy.note::6a3f0c8d][6a3f0c8d]]
+/// Return the point at arc length `s` along the polyline through
+/// `points` with cumulative lengths in `lengths`.
+fn polyline_point_at(points: &[Vec3], lengths: &[f32], s: f32) -> (usize, Vec3) {
+    let k = lengths.partition_point(|&x| x < s).clamp(1, points.len() - 1);
+    let l = lengths[k] - lengths[k - 1];
+    let t = if l > 0.0 { (s - lengths[k - 1]) / l } else { 0.0 };
+    (k, points[k - 1].lerp(points[k], t))
+}
+
+/// Create dashed line along the polyline through `points`, which could
+/// be a straight line or an arc approximated by short segments.
+pub fn create_dashed_line(
+    points: &[Vec3],
+    radius: f32,
+    color: Color,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
+) -> Vec<PbrBundle> {
+    // the lengths of dash and dash + gap
+    const DASH: f32 = 0.12;
+    const PERIOD: f32 = 0.2;
+
+    if points.len() < 2 {
+        return vec![];
+    }
+    let mut lengths = vec![0.0];
+    for w in points.windows(2) {
+        lengths.push(lengths.last().unwrap() + w[0].distance(w[1]));
+    }
+    let total = *lengths.last().unwrap();
+    let mut assets = (meshes, materials, cache);
+    let mut dashes = vec![];
+    let mut s0 = 0.0;
+    while s0 < total {
+        let s1 = (s0 + DASH).min(total);
+        // the dash may cross vertices of the polyline
+        let (k0, p0) = polyline_point_at(points, &lengths, s0);
+        let (k1, p1) = polyline_point_at(points, &lengths, s1);
+        let mut path = vec![p0];
+        path.extend_from_slice(&points[k0..k1]);
+        path.push(p1);
+        for w in path.windows(2) {
+            if w[0].distance(w[1]) > 1e-4 {
+                dashes.push(create_line_segment(w[0], w[1], true, radius, color, &mut assets));
+            }
+        }
+        s0 += PERIOD;
     }
+    dashes
 }
-// 14379cd1 ends here
+// 6a3f0c8d ends here
 
 // [[file:../bevy.note::52696eea][52696eea]]
 #[derive(Resource, Clone, Debug, Default)]
@@ -84,6 +254,169 @@ fn get_atom_display_size(a: &gchemol::Atom) -> f64 {
 }
 // 5cf783bd ends here
 
+// [[file:../bevy.note::7c1e0a94][7c1e0a94]]
+/// How atoms and bonds are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, enum_iterator::Sequence)]
+pub enum Representation {
+    /// Atoms in reduced covalent radii connected by thin sticks
+    #[default]
+    BallAndStick,
+    /// Atoms in van der Waals radii without bonds
+    SpaceFilling,
+    /// Atoms and bonds in the same radius
+    Licorice,
+    /// Bonds drawn as thin lines
+    Wireframe,
+}
+
+impl Representation {
+    /// Return the name for display in ui.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BallAndStick => "Ball and stick",
+            Self::SpaceFilling => "Space filling",
+            Self::Licorice => "Licorice",
+            Self::Wireframe => "Wireframe",
+        }
+    }
+
+    /// Return the display radius of atom `a`. User defined radius in
+    /// `style` applies to ball-and-stick representation.
+    fn atom_radius(&self, a: &gchemol::Atom, style: &ElementStyle) -> f32 {
+        match self {
+            Self::BallAndStick => style.radius.unwrap_or(get_atom_display_size(a) as f32),
+            Self::SpaceFilling => a.get_vdw_radius().unwrap_or(1.5) as f32,
+            // make joints of bonds smooth
+            Self::Licorice | Self::Wireframe => self.bond_radius(style).unwrap_or_default(),
+        }
+    }
+
+    /// Return the radius of bonds. Return None if bonds are hidden. User
+    /// defined bond radius in `style` applies to ball-and-stick
+    /// representation.
+    fn bond_radius(&self, style: &ElementStyle) -> Option<f32> {
+        match self {
+            Self::BallAndStick => style.bond_radius.or(Some(DEFAULT_BOND_RADIUS)),
+            Self::SpaceFilling => None,
+            Self::Licorice => Some(0.15),
+            Self::Wireframe => Some(0.03),
+        }
+    }
+}
+
+/// Representations of displayed atoms
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RepresentationSettings {
+    /// The representation for all atoms
+    global: Representation,
+    /// Representations for part of atoms, overriding `global`
+    atoms: HashMap<usize, Representation>,
+    /// Draw each bond as two halves in the colors of bonded atoms
+    half_bonds: bool,
+    /// User defined colors and radii of elements
+    styles: ElementStyles,
+    /// Display periodic images and bonds crossing cell boundary
+    periodic: bool,
+    /// Display image atoms within this margin outside the unit cell, in
+    /// fractional coordinates
+    image_margin: f64,
+    /// Symbols of central elements for drawing coordination polyhedra
+    polyhedra: std::collections::BTreeSet<String>,
+}
+
+impl RepresentationSettings {
+    /// Return the representation of atom `sn`.
+    pub fn get(&self, sn: usize) -> Representation {
+        self.atoms.get(&sn).copied().unwrap_or(self.global)
+    }
+
+    /// Return the representation for all atoms, or None if some atoms
+    /// have their own representations.
+    pub fn get_global(&self) -> Option<Representation> {
+        self.atoms.is_empty().then_some(self.global)
+    }
+
+    /// Set the representation of all atoms to `repr`.
+    pub fn set_global(&mut self, repr: Representation) {
+        self.global = repr;
+        self.atoms.clear();
+    }
+
+    /// Set the representation of `atoms` to `repr`.
+    pub fn set_atoms(&mut self, atoms: &[usize], repr: Representation) {
+        for &i in atoms {
+            self.atoms.insert(i, repr);
+        }
+    }
+
+    /// Remove representations for individual atoms.
+    pub fn clear_atoms(&mut self) {
+        self.atoms.clear();
+    }
+
+    /// Return true if bonds are drawn in two colors of bonded atoms.
+    pub fn half_bonds(&self) -> bool {
+        self.half_bonds
+    }
+
+    /// Draw bonds in two colors of bonded atoms or not.
+    pub fn set_half_bonds(&mut self, half_bonds: bool) {
+        self.half_bonds = half_bonds;
+    }
+
+    /// Set user defined element styles.
+    pub fn with_styles(mut self, styles: ElementStyles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    /// Return user defined element styles.
+    pub fn styles(&self) -> &ElementStyles {
+        &self.styles
+    }
+
+    /// Replace user defined element styles with `styles`.
+    pub fn set_styles(&mut self, styles: ElementStyles) {
+        self.styles = styles;
+    }
+
+    /// Return the margin for displaying periodic images if periodic
+    /// display is enabled.
+    pub fn periodic(&self) -> Option<f64> {
+        self.periodic.then_some(self.image_margin)
+    }
+
+    /// Enable periodic display with image atoms within `margin`
+    /// outside the unit cell, or disable it if `margin` is None.
+    pub fn set_periodic(&mut self, margin: Option<f64>) {
+        self.periodic = margin.is_some();
+        if let Some(margin) = margin {
+            self.image_margin = margin;
+        }
+    }
+
+    /// Return true if coordination polyhedra are drawn around atoms of
+    /// element `symbol`.
+    pub fn has_polyhedra(&self, symbol: &str) -> bool {
+        self.polyhedra.contains(symbol)
+    }
+
+    /// Draw coordination polyhedra around atoms of element `symbol` or not.
+    pub fn set_polyhedra(&mut self, symbol: &str, enabled: bool) {
+        if enabled {
+            self.polyhedra.insert(symbol.to_owned());
+        } else {
+            self.polyhedra.remove(symbol);
+        }
+    }
+
+    /// Return true if `mol` is displayed with periodic images.
+    pub fn is_periodic(&self, mol: &gchemol::Molecule) -> bool {
+        self.periodic && mol.lattice.is_some()
+    }
+}
+// 7c1e0a94 ends here
+
 // [[file:../bevy.note::4f2c9201][4f2c9201]]
 fn get_atom_color(atom: &gchemol::Atom) -> Color {
     // initialize atom colors
@@ -208,8 +541,137 @@ fn get_atom_color(atom: &gchemol::Atom) -> Color {
         atom_colors[n]
     }
 }
+
+/// Return the washed-out `color` for drawing frozen atoms.
+fn desaturate(color: Color) -> Color {
+    match color.as_hsla() {
+        Color::Hsla {
+            hue,
+            saturation,
+            lightness,
+            alpha,
+        } => Color::hsla(hue, saturation * 0.2, lightness * 0.7 + 0.15, alpha),
+        other => other,
+    }
+}
 // 4f2c9201 ends here
 
+// [[file:../bevy.note::e8a3f51c][e8a3f51c]]
+use gut::prelude::{Configure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The default radius of bonds in ball-and-stick representation
+const DEFAULT_BOND_RADIUS: f32 = 0.07;
+
+/// Return the directory for user config files.
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|d| PathBuf::from(d).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(dir.join("gchemol-view"))
+}
+
+/// User defined display style of an element. Unset fields fall back to
+/// the defaults.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ElementStyle {
+    /// The color in RGB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[u8; 3]>,
+    /// The radius of atom in ball-and-stick representation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius: Option<f32>,
+    /// The radius of bonds in ball-and-stick representation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bond_radius: Option<f32>,
+}
+
+impl ElementStyle {
+    /// Return the built-in style of element `symbol`.
+    pub fn builtin(symbol: &str) -> Self {
+        let a = gchemol::Atom::new(symbol, [0.0; 3]);
+        let [r, g, b, _] = get_atom_color(&a).as_rgba_f32().map(|x| (x * 255.0).round() as u8);
+        Self {
+            color: Some([r, g, b]),
+            radius: Some(get_atom_display_size(&a) as f32),
+            bond_radius: Some(DEFAULT_BOND_RADIUS),
+        }
+    }
+}
+
+/// User defined element styles, which can be loaded from a config file
+/// in TOML or JSON format, for example:
+///
+/// ```toml
+/// [elements.H]
+/// color = [255, 255, 255]
+/// radius = 0.2
+/// bond_radius = 0.05
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ElementStyles {
+    /// Styles keyed by element symbol
+    #[serde(default)]
+    pub elements: BTreeMap<String, ElementStyle>,
+}
+
+impl ElementStyles {
+    /// Return the style of element `symbol`.
+    pub fn get(&self, symbol: &str) -> ElementStyle {
+        self.elements.get(symbol).copied().unwrap_or_default()
+    }
+
+    /// Return the path to user config file.
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|d| d.join("elements.toml"))
+    }
+
+    /// Load element styles from config file in `path`. JSON format is
+    /// assumed for file with `.json` extension, TOML otherwise.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let s = gut::fs::read_file(path)?;
+        if path.extension().is_some_and(|x| x == "json") {
+            Self::from_json(&s)
+        } else {
+            Self::from_toml(&s)
+        }
+    }
+
+    /// Save element styles into config file in `path`.
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        let s = if path.extension().is_some_and(|x| x == "json") {
+            self.to_json()?
+        } else {
+            self.to_toml()?
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        gut::fs::write_to_file(path, &s)
+    }
+
+    /// Load element styles from user config file if it exists.
+    pub fn load() -> Self {
+        let Some(path) = Self::default_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        match Self::from_file(&path) {
+            Ok(styles) => {
+                info!("Loaded element styles from {path:?}");
+                styles
+            }
+            Err(err) => {
+                error!("Failed to load element styles: {err:?}");
+                Self::default()
+            }
+        }
+    }
+}
+// e8a3f51c ends here
+
 // [[file:../bevy.note::0b92cef9][0b92cef9]]
 #[derive(Clone, Copy, Debug, Component)]
 pub struct AtomIndex(pub usize);
@@ -217,6 +679,10 @@ pub struct AtomIndex(pub usize);
 /// Represent the displayed atom in 3D viewer
 #[derive(Clone, Debug, Component)]
 pub struct Atom {
+    /// The element number
+    element: usize,
+    /// The radius of bonds to this atom, None if bonds are hidden
+    bond_radius: Option<f32>,
     color: Color,
     visible: bool,
     radius: f32,
@@ -226,12 +692,20 @@ pub struct Atom {
 }
 
 impl Atom {
-    pub fn new(a: &gchemol::Atom) -> Self {
-        let radius = get_atom_display_size(a) as f32;
-        let color = get_atom_color(a);
+    pub fn new(a: &gchemol::Atom, repr: Representation, styles: &ElementStyles) -> Self {
+        let style = styles.get(a.symbol());
+        let radius = repr.atom_radius(a, &style);
+        let color = style
+            .color
+            .map(|[r, g, b]| Color::rgb_u8(r, g, b))
+            .unwrap_or(get_atom_color(a));
+        // frozen atoms are drawn distinctly in any freezing axis
+        let color = if a.freezing().contains(&true) { desaturate(color) } else { color };
         let position = a.position().map(|v| v as f32).into();
 
         Self {
+            element: a.number(),
+            bond_radius: repr.bond_radius(&style),
             position,
             color,
             radius,
@@ -240,14 +714,16 @@ impl Atom {
         }
     }
 
+    /// Display the atom at `position`, e.g. for periodic images.
+    pub fn with_position(mut self, position: Vec3) -> Self {
+        self.position = position;
+        self
+    }
+
     /// Return the text of atom label.
     pub fn get_label(&self, sn: usize) -> String {
         self.label.clone().unwrap_or(sn.to_string())
     }
-
-    pub fn set_visible(&mut self, visible: bool) {
-        self.visible = visible;
-    }
 }
 
 #[derive(Bundle)]
@@ -257,16 +733,18 @@ pub struct AtomBundle {
 }
 
 impl AtomBundle {
-    pub fn new(atom: Atom, mut meshes: &mut ResMut<Assets<Mesh>>, mut materials: &mut ResMut<Assets<StandardMaterial>>) -> Self {
+    pub fn new(
+        atom: Atom,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        cache: &mut AssetCache,
+    ) -> Self {
         let visibility = visibility(atom.visible);
 
         Self {
             pbr: PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::UVSphere {
-                    radius: atom.radius,
-                    ..Default::default()
-                })),
-                material: materials.add(atom.color.into()),
+                mesh: cache.sphere(atom.element, atom.radius, meshes),
+                material: cache.material(atom.color, materials),
                 transform: Transform::from_translation(atom.position),
                 visibility,
                 ..default()
@@ -287,43 +765,194 @@ impl AtomBundle {
 // 0b92cef9 ends here
 
 // [[file:../bevy.note::5a5c8b3f][5a5c8b3f]]
+/// The serial numbers of two bonded atoms
 #[derive(Clone, Copy, Debug, Component)]
-pub struct BondIndex(usize);
+pub struct BondIndex(pub usize, pub usize);
 
 #[derive(Clone, Debug, Component)]
 pub struct Bond {
     atom1: Atom,
     atom2: Atom,
+    kind: gchemol::BondKind,
+    /// Draw two halves in the colors of bonded atoms
+    half: bool,
     visible: bool,
 }
 
+/// Half of a bond drawn in the color of the atom nearby
+#[derive(Clone, Copy, Debug, Component)]
+pub struct HalfBond {
+    /// True if the half is next to the first atom of the bond
+    pub first: bool,
+    /// The color of the atom nearby
+    pub color: Color,
+}
+
 impl Bond {
     pub fn new(atom1: Atom, atom2: Atom) -> Self {
         Self {
             atom1,
             atom2,
+            kind: gchemol::BondKind::Single,
+            half: false,
             visible: true,
         }
     }
 
-    pub fn set_visible(&mut self, visible: bool) {
-        self.visible = visible;
+    /// Draw the bond in two halves colored by bonded atoms.
+    pub fn with_half_bonds(mut self, half: bool) -> Self {
+        self.half = half;
+        self
+    }
+
+    /// Set bond kind for drawing bond order.
+    pub fn with_kind(mut self, kind: gchemol::BondKind) -> Self {
+        self.kind = kind;
+        self
     }
+
+    /// Return the radius of bond according to the representations of
+    /// bonded atoms. Return None if the bond is hidden.
+    fn radius(&self) -> Option<f32> {
+        match (self.atom1.bond_radius, self.atom2.bond_radius) {
+            (Some(ri), Some(rj)) => Some(ri.min(rj)),
+            (ri, rj) => ri.or(rj),
+        }
+    }
+
+    /// Return cylinders for drawing the bond in its local coordinate
+    /// system, in which the bond spans from y = -0.5 to y = 0.5, and
+    /// the x axis lies in the plane of neighboring atoms. The cylinders
+    /// of half bonds are returned with `HalfBond` marked.
+    fn cylinders(
+        &self,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        cache: &mut AssetCache,
+    ) -> Vec<(PbrBundle, Option<HalfBond>)> {
+        use gchemol::BondKind::*;
+
+        let Some(r) = self.radius() else {
+            return vec![];
+        };
+        // offset along x axis, radius, and dashed or not
+        let parts = match self.kind {
+            Double => vec![(-r, 0.6 * r, false), (r, 0.6 * r, false)],
+            Triple => vec![(-1.4 * r, 0.5 * r, false), (0.0, 0.5 * r, false), (1.4 * r, 0.5 * r, false)],
+            Quadruple => vec![
+                (-2.1 * r, 0.4 * r, false),
+                (-0.7 * r, 0.4 * r, false),
+                (0.7 * r, 0.4 * r, false),
+                (2.1 * r, 0.4 * r, false),
+            ],
+            // the dashed part lies on the side of neighboring atoms, i.e. inside the ring
+            Aromatic | Partial => vec![(0.0, r, false), (1.8 * r, 0.5 * r, true)],
+            Single | Dummy => vec![(0.0, r, false)],
+        };
+
+        let mut cylinders = vec![];
+        for (x, radius, dashed) in parts {
+            let mesh = cache.cylinder(radius, meshes);
+            // the (y, length) of solid or dashed line segments
+            let segments: Vec<(f32, f32)> = if dashed {
+                const NDASH: usize = 5;
+                let length = 0.7 / (2 * NDASH - 1) as f32;
+                (0..NDASH)
+                    .map(|k| (-0.35 + length * (2 * k) as f32 + length / 2.0, length))
+                    .collect()
+            } else if self.half {
+                vec![(-0.25, 0.5), (0.25, 0.5)]
+            } else {
+                vec![(0.0, 1.0)]
+            };
+            for (y, length) in segments {
+                // the first atom lies at y = -0.5
+                let half = self.half.then(|| {
+                    let first = y < 0.0;
+                    let color = if first { self.atom1.color } else { self.atom2.color };
+                    HalfBond { first, color }
+                });
+                let color = half.map(|h| h.color).unwrap_or(Color::GRAY);
+                let pbr = PbrBundle {
+                    mesh: mesh.clone(),
+                    material: cache.material(color, materials),
+                    transform: Transform::from_xyz(x, y, 0.0).with_scale(Vec3::new(1.0, length, 1.0)),
+                    ..default()
+                };
+                cylinders.push((pbr, half));
+            }
+        }
+        cylinders
+    }
+}
+
+/// Return the neighboring atom for placing multiple bond between atom
+/// `i` and `j` in a plane.
+fn find_plane_atom(mol: &gchemol::Molecule, i: usize, j: usize) -> Option<usize> {
+    use gchemol::BondKind::*;
+
+    let kind = mol.get_bond(i, j)?.kind();
+    if matches!(kind, Single | Dummy) {
+        return None;
+    }
+
+    // prefer the neighbor bonded in the same kind, e.g. in aromatic rings
+    let neighbors: Vec<_> = mol
+        .connected(i)
+        .filter(|&k| k != j)
+        .map(|k| (i, k))
+        .chain(mol.connected(j).filter(|&k| k != i).map(|k| (j, k)))
+        .collect();
+    let found = neighbors
+        .iter()
+        .find(|&&(a, k)| mol.get_bond(a, k).map(|b| b.kind()) == Some(kind))
+        .or(neighbors.first());
+    found.map(|&(_, k)| k)
+}
+
+/// Return the transform of bond from `pi` to `pj`. The x axis is
+/// rotated into the plane of `pk` if any.
+fn bond_transform_at(pi: Vec3, pj: Vec3, pk: Option<Vec3>) -> Transform {
+    let mut transform = line_segment_transform(pi, pj);
+    if let Some(pk) = pk {
+        let y = (pj - pi).normalize();
+        let v = pk - pi;
+        let x = v - v.dot(y) * y;
+        // ignore neighbors in line with the bond
+        if x.length() > 1e-3 {
+            let x = x.normalize();
+            transform.rotation = Quat::from_mat3(&Mat3::from_cols(x, y, x.cross(y)));
+        }
+    }
+    transform
+}
+
+/// Return the transform of bond between atom `i` and `j` in `mol`. For
+/// multiple bonds, the x axis is rotated into the plane of a neighboring
+/// atom.
+fn bond_transform(mol: &gchemol::Molecule, i: usize, j: usize) -> Transform {
+    let pi = as_vec3(mol.get_atom_unchecked(i).position());
+    let pj = as_vec3(mol.get_atom_unchecked(j).position());
+    let pk = find_plane_atom(mol, i, j).map(|k| as_vec3(mol.get_atom_unchecked(k).position()));
+    bond_transform_at(pi, pj, pk)
 }
 
 #[derive(Bundle)]
 pub struct BondBundle {
-    pbr: PbrBundle,
+    spatial: SpatialBundle,
     bond: Bond,
 }
 
 impl BondBundle {
-    pub fn new(bond: Bond, mut meshes: &mut ResMut<Assets<Mesh>>, mut materials: &mut ResMut<Assets<StandardMaterial>>) -> Self {
-        let pi = bond.atom1.position;
-        let pj = bond.atom2.position;
-        let pbr = create_line_segment(pi, pj, bond.visible, meshes, materials, 0.07, Color::GRAY);
+    pub fn new(bond: Bond, transform: Transform) -> Self {
+        let visible = bond.visible && bond.radius().is_some();
+        let spatial = SpatialBundle {
+            visibility: visibility(visible),
+            transform,
+            ..default()
+        };
 
-        Self { pbr, bond }
+        Self { spatial, bond }
     }
 }
 // 5a5c8b3f ends here
@@ -337,7 +966,7 @@ pub struct Molecule;
 #[derive(Clone, Debug, Component)]
 pub struct Lattice;
 
-fn as_vec3(p: impl Into<[f64; 3]>) -> Vec3 {
+pub fn as_vec3(p: impl Into<[f64; 3]>) -> Vec3 {
     let p = p.into();
     Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32)
 }
@@ -345,8 +974,9 @@ fn as_vec3(p: impl Into<[f64; 3]>) -> Vec3 {
 fn create_lattice(
     lat: &gchemol::Lattice,
     visible: bool,
-    mut meshes: &mut ResMut<Assets<Mesh>>,
-    mut materials: &mut ResMut<Assets<StandardMaterial>>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
 ) -> [PbrBundle; 12] {
     let p0 = lat.to_cart([0.0, 0.0, 0.0]);
     let p1 = lat.to_cart([1.0, 0.0, 0.0]);
@@ -366,23 +996,312 @@ fn create_lattice(
     let p7 = as_vec3(p7);
 
     let radius = 0.03;
+    let mut assets = (meshes, materials, cache);
     [
-        create_line_segment(p0, p1, visible, meshes, materials, radius, Color::RED),
-        create_line_segment(p0, p2, visible, meshes, materials, radius, Color::YELLOW),
-        create_line_segment(p0, p3, visible, meshes, materials, radius, Color::BLUE),
-        create_line_segment(p1, p4, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p1, p5, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p2, p4, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p2, p6, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p3, p5, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p3, p6, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p7, p4, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p7, p5, visible, meshes, materials, radius, Color::WHITE),
-        create_line_segment(p7, p6, visible, meshes, materials, radius, Color::WHITE),
+        create_line_segment(p0, p1, visible, radius, Color::RED, &mut assets),
+        create_line_segment(p0, p2, visible, radius, Color::YELLOW, &mut assets),
+        create_line_segment(p0, p3, visible, radius, Color::BLUE, &mut assets),
+        create_line_segment(p1, p4, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p1, p5, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p2, p4, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p2, p6, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p3, p5, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p3, p6, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p7, p4, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p7, p5, visible, radius, Color::WHITE, &mut assets),
+        create_line_segment(p7, p6, visible, radius, Color::WHITE, &mut assets),
     ]
 }
 // 38660d10 ends here
 
+// [[file:../bevy.note::b2f6c1e9][b2f6c1e9]]
+/// Coordination polyhedron around a central atom, indexed in
+/// `PolyhedraDisplay`
+#[derive(Clone, Copy, Debug, Component)]
+pub struct Polyhedron(pub usize);
+
+/// Return faces of convex hull of `points`. Each face is a polygon in
+/// counter-clockwise order viewed from outside.
+fn convex_hull_faces(points: &[Vec3]) -> Vec<Vec<usize>> {
+    use std::collections::HashSet;
+
+    const EPS: f32 = 1e-3;
+    let n = points.len();
+    let mut faces = vec![];
+    let mut found = HashSet::new();
+    for a in 0..n {
+        for b in a + 1..n {
+            for c in b + 1..n {
+                let normal = (points[b] - points[a]).cross(points[c] - points[a]);
+                if normal.length() < EPS {
+                    continue;
+                }
+                let mut normal = normal.normalize();
+                let sides: Vec<_> = points.iter().map(|p| normal.dot(*p - points[a])).collect();
+                let above = sides.iter().any(|&x| x > EPS);
+                let below = sides.iter().any(|&x| x < -EPS);
+                if above && below {
+                    continue;
+                }
+                // make the normal point outwards
+                if above {
+                    normal = -normal;
+                }
+                // merge coplanar points into one face
+                let face: Vec<_> = (0..n).filter(|&k| sides[k].abs() <= EPS).collect();
+                if !found.insert(face.clone()) {
+                    continue;
+                }
+                let center = face.iter().map(|&k| points[k]).sum::<Vec3>() / face.len() as f32;
+                let u = (points[face[0]] - center).normalize();
+                let v = normal.cross(u);
+                let angle = |k: usize| {
+                    let d = points[k] - center;
+                    d.dot(v).atan2(d.dot(u))
+                };
+                let mut face = face;
+                face.sort_by(|&i, &j| angle(i).total_cmp(&angle(j)));
+                faces.push(face);
+            }
+        }
+    }
+    faces
+}
+
+/// Create mesh of the convex polyhedron formed by `points`. Return None
+/// if `points` are too few or coplanar.
+fn create_polyhedron_mesh(points: &[Vec3]) -> Option<Mesh> {
+    let faces = convex_hull_faces(points);
+    // a closed polyhedron has at least 4 faces
+    if faces.len() < 4 {
+        return None;
+    }
+    Some(create_faces_mesh(points, &faces))
+}
+
+/// Create mesh of convex polygon `faces` with vertices in `points`.
+fn create_faces_mesh(points: &[Vec3], faces: &[Vec<usize>]) -> Mesh {
+    use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut indices = vec![];
+    for face in faces {
+        let p0 = points[face[0]];
+        let normal = (points[face[1]] - p0).cross(points[face[2]] - p0).normalize();
+        let start = positions.len() as u32;
+        for &k in face {
+            positions.push(points[k].to_array());
+            normals.push(normal.to_array());
+        }
+        // triangulate the convex polygon as a fan
+        for k in 1..face.len() as u32 - 1 {
+            indices.extend([start, start + k, start + k + 1]);
+        }
+    }
+    let uvs = vec![[0.0f32; 2]; positions.len()];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Translucent coordination polyhedra formed by bonded neighbors of
+/// central atoms. The neighbors are found once for a topology, and the
+/// meshes are updated in place for new atom positions.
+#[derive(Clone, Debug, Default, Component)]
+pub struct PolyhedraDisplay {
+    /// Central atoms with their bonded neighbors
+    centers: Vec<(usize, Vec<usize>)>,
+    /// Find neighbors across cell boundary in minimum image convention
+    periodic: bool,
+}
+
+impl PolyhedraDisplay {
+    /// Find bonded neighbors of central atoms in elements enabled in
+    /// `repr`. For crystals, neighbors across cell boundary are found in
+    /// minimum image convention.
+    pub fn new(mol: &gchemol::Molecule, repr: &RepresentationSettings) -> Self {
+        let centers: Vec<_> = mol
+            .atoms()
+            .filter_map(|(i, a)| repr.has_polyhedra(a.symbol()).then_some(i))
+            .collect();
+        if centers.is_empty() {
+            return Self::default();
+        }
+
+        let periodic = mol.get_lattice().is_some();
+        let pbc_mol = periodic.then(|| {
+            let mut mol = mol.clone();
+            mol.rebond();
+            mol
+        });
+        let bonded = pbc_mol.as_ref().unwrap_or(mol);
+        let centers = centers.into_iter().map(|i| (i, bonded.connected(i).collect())).collect();
+        Self { centers, periodic }
+    }
+
+    /// Return true if no polyhedra to draw.
+    pub fn is_empty(&self) -> bool {
+        self.centers.is_empty()
+    }
+
+    /// Return the mesh of polyhedron `k` for atom positions in `mol`.
+    fn mesh(&self, mol: &gchemol::Molecule, k: usize) -> Option<Mesh> {
+        use bevy::math::DVec3;
+
+        let (i, neighbors) = &self.centers[k];
+        let pi = mol.get_atom_unchecked(*i).position();
+        let points: Vec<_> = match mol.get_lattice().filter(|_| self.periodic) {
+            Some(lat) => neighbors
+                .iter()
+                .map(|&j| {
+                    let pj = mol.get_atom_unchecked(j).position();
+                    let d = DVec3::from_array(pj) - DVec3::from_array(pi);
+                    as_vec3(pi) + as_vec3(lat.apply_mic(d.to_array()))
+                })
+                .collect(),
+            None => neighbors
+                .iter()
+                .map(|&j| as_vec3(mol.get_atom_unchecked(j).position()))
+                .collect(),
+        };
+        create_polyhedron_mesh(&points)
+    }
+
+    /// Spawn the polyhedra in colors of central atoms.
+    pub fn spawn(
+        &self,
+        mol: &gchemol::Molecule,
+        repr: &RepresentationSettings,
+        commands: &mut ChildBuilder,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        cache: &mut AssetCache,
+    ) {
+        for (k, &(i, _)) in self.centers.iter().enumerate() {
+            if let Some(mesh) = self.mesh(mol, k) {
+                let color = Atom::new(mol.get_atom_unchecked(i), repr.get(i), repr.styles()).color;
+                commands
+                    .spawn(PbrBundle {
+                        mesh: meshes.add(mesh),
+                        material: cache.translucent(color, materials),
+                        ..default()
+                    })
+                    .insert(Polyhedron(k));
+            }
+        }
+    }
+
+    /// Update `mesh` of `polyhedron` for atom positions in `mol`. The
+    /// mesh is kept if the neighbors become coplanar.
+    pub fn update_mesh(&self, mol: &gchemol::Molecule, polyhedron: &Polyhedron, mesh: &mut Mesh) {
+        if let Some(new_mesh) = self.mesh(mol, polyhedron.0) {
+            *mesh = new_mesh;
+        }
+    }
+}
+// b2f6c1e9 ends here
+
+// [[file:../bevy.note::4e9a7d15][4e9a7d15]]
+/// Lattice plane of Miller indices clipped to the unit cell
+#[derive(Clone, Copy, Debug, Component)]
+pub struct LatticePlane;
+
+/// Return the Miller indices `hkl` in the cell of `lat`, given `hkl` of
+/// the cell before replicating it into a `supercell`.
+fn miller_indices_in_supercell(hkl: [i32; 3], supercell: [u32; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|k| (hkl[k] * supercell[k] as i32) as f64)
+}
+
+/// Return the interplanar spacing of lattice planes `hkl` of the cell
+/// before replicating it into a `supercell` of `lat`. Return None if
+/// `hkl` are all zeros.
+pub fn interplanar_spacing(lat: &gchemol::Lattice, hkl: [i32; 3], supercell: [u32; 3]) -> Option<f64> {
+    use bevy::math::{DMat3, DVec3};
+
+    if hkl == [0; 3] {
+        return None;
+    }
+    let h = DVec3::from_array(miller_indices_in_supercell(hkl, supercell));
+    // the rows of inverse matrix are reciprocal vectors
+    let inv = DMat3::from_cols_slice(lat.inv_matrix().as_slice());
+    let g = inv.transpose() * h;
+    Some(1.0 / g.length())
+}
+
+/// Create translucent lattice planes `hkl` clipped to the unit cell of
+/// `lat`, with `offset` along the plane normal in units of interplanar
+/// spacing. The `hkl` refers to the cell before replicating it into a
+/// `supercell`, so all planes in the family crossing the cell are
+/// created.
+pub fn create_lattice_planes(
+    lat: &gchemol::Lattice,
+    hkl: [i32; 3],
+    offset: f64,
+    supercell: [u32; 3],
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
+) -> Vec<PbrBundle> {
+    use bevy::math::DVec3;
+
+    if hkl == [0; 3] {
+        return vec![];
+    }
+    let h = DVec3::from_array(miller_indices_in_supercell(hkl, supercell));
+    // cube edges in fractional coordinates: start point and direction
+    let mut edges = vec![];
+    for k in 0..3 {
+        let dir = DVec3::AXES[k];
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            let mut start = DVec3::ZERO;
+            start[(k + 1) % 3] = u;
+            start[(k + 2) % 3] = v;
+            edges.push((start, dir));
+        }
+    }
+    // the range of h·f in the cell
+    let fmin = h.min(DVec3::ZERO).dot(DVec3::ONE);
+    let fmax = h.max(DVec3::ZERO).dot(DVec3::ONE);
+    let nmin = (fmin - offset).floor() as i32;
+    let nmax = (fmax - offset).ceil() as i32;
+
+    let material = cache.translucent(Color::PURPLE, materials);
+    let mut planes = vec![];
+    for n in nmin..=nmax {
+        let c = offset + n as f64;
+        // intersections of plane h·f = c with cell edges
+        let mut points: Vec<Vec3> = vec![];
+        for &(start, dir) in &edges {
+            let hd = h.dot(dir);
+            if hd.abs() < 1e-8 {
+                continue;
+            }
+            let t = (c - h.dot(start)) / hd;
+            if (-1e-8..=1.0 + 1e-8).contains(&t) {
+                let p = as_vec3(lat.to_cart((start + t * dir).to_array()));
+                if points.iter().all(|q| q.distance(p) > 1e-4) {
+                    points.push(p);
+                }
+            }
+        }
+        let faces = convex_hull_faces(&points);
+        if points.len() >= 3 && faces.len() == 1 {
+            planes.push(PbrBundle {
+                mesh: meshes.add(create_faces_mesh(&points, &faces)),
+                material: material.clone(),
+                ..default()
+            });
+        }
+    }
+    planes
+}
+// 4e9a7d15 ends here
+
 // [[file:../bevy.note::c989001a][c989001a]]
 // https://github.com/aevyrie/bevy_mod_picking/blob/main/examples/tinted_highlight.rs
 use bevy::math::vec4;
@@ -403,58 +1322,388 @@ const HIGHLIGHT_TINT: Highlight<StandardMaterial> = Highlight {
 // c989001a ends here
 
 // [[file:../bevy.note::d5c13162][d5c13162]]
+/// The index of trajectory frame being displayed
 #[derive(Clone, Copy, Debug, Component)]
 pub struct FrameIndex(pub usize);
 
-pub fn spawn_molecule(
+/// The atoms, bonds and lattice of displayed molecule. The displayed
+/// entities are reused for another frame with the same atoms, and
+/// bonds or lattice are recreated only when they change.
+#[derive(Clone, Debug, PartialEq, Component)]
+pub struct Topology {
+    /// atom serial numbers with element numbers and freezing flags,
+    /// which affect materials of atoms
+    atoms: Vec<(usize, usize, [bool; 3])>,
+    /// serial numbers of bonded atoms in pairs with bond kinds
+    bonds: Vec<(usize, usize, gchemol::BondKind)>,
+    /// lattice vectors and origin
+    lattice: Option<([[f64; 3]; 3], [f64; 3])>,
+}
+
+impl Topology {
+    pub fn new(mol: &gchemol::Molecule) -> Self {
+        let atoms = mol.atoms().map(|(i, a)| (i, a.number(), a.freezing())).collect();
+        let mut bonds: Vec<_> = mol.bonds().map(|(i, j, b)| (i.min(j), i.max(j), b.kind())).collect();
+        bonds.sort_unstable_by_key(|&(i, j, _)| (i, j));
+        let lattice = mol.get_lattice().map(|lat| (lat.vectors().map(|v| v.into()), lat.origin().into()));
+        Self { atoms, bonds, lattice }
+    }
+
+    /// Return true if atom entities can be reused for `other`.
+    pub fn same_atoms(&self, other: &Self) -> bool {
+        self.atoms == other.atoms
+    }
+
+    /// Return true if bond entities can be reused for `other`.
+    pub fn same_bonds(&self, other: &Self) -> bool {
+        self.bonds == other.bonds
+    }
+
+    /// Return true if lattice entities can be reused for `other`.
+    pub fn same_lattice(&self, other: &Self) -> bool {
+        self.lattice == other.lattice
+    }
+}
+
+pub fn spawn_atoms(
     mol: &gchemol::Molecule,
-    visible: bool,
-    frame_index: usize,
-    mut commands: &mut Commands,
-    mut meshes: &mut ResMut<Assets<Mesh>>,
-    mut materials: &mut ResMut<Assets<StandardMaterial>>,
+    repr: &RepresentationSettings,
+    commands: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
 ) {
-    let frame_name = FrameIndex(frame_index);
+    for (i, a) in mol.atoms() {
+        let atom = Atom::new(a, repr.get(i), repr.styles());
+        let highlight = cache.highlight(atom.color, materials);
+        let atom_bundle = AtomBundle::new(atom, meshes, materials, cache);
+        commands
+            .spawn(atom_bundle)
+            .insert(AtomIndex(i))
+            .insert(PickableBundle::default())
+            .insert(RaycastPickTarget::default())
+            .insert(highlight);
+    }
+}
+
+pub fn spawn_bonds(
+    mol: &gchemol::Molecule,
+    repr: &RepresentationSettings,
+    commands: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
+) {
+    // bonds in periodic display are drawn with `PeriodicDisplay`
+    if repr.is_periodic(mol) {
+        return;
+    }
+
+    for (i, j, b) in mol.bonds() {
+        let ai = mol.get_atom_unchecked(i);
+        let aj = mol.get_atom_unchecked(j);
+        let atom1 = Atom::new(ai, repr.get(i), repr.styles());
+        let atom2 = Atom::new(aj, repr.get(j), repr.styles());
+        let bond = Bond::new(atom1, atom2).with_kind(b.kind()).with_half_bonds(repr.half_bonds());
+        let transform = bond_transform(mol, i, j);
+        spawn_bond(bond, transform, BondIndex(i, j), commands, meshes, materials, cache);
+    }
+}
+
+fn spawn_bond(
+    bond: Bond,
+    transform: Transform,
+    index: impl Bundle,
+    commands: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
+) {
+    let cylinders = bond.cylinders(meshes, materials, cache);
     commands
-        .spawn(SpatialBundle::default())
-        .insert(Molecule)
-        // .insert(crate::animation::Frame(frame_index))
-        // for animation control
+        .spawn(BondBundle::new(bond, transform))
+        .insert(index)
         .with_children(|commands| {
-            // spawn atoms
-            for (i, a) in mol.atoms() {
-                let mut atom = Atom::new(a);
-                atom.set_visible(visible);
-                let mut atom_bundle = AtomBundle::new(atom, &mut meshes, &mut materials);
-                commands
-                    .spawn(atom_bundle)
-                    .insert(AtomIndex(i))
-                    .insert(frame_name)
-                    .insert(PickableBundle::default())
-                    .insert(RaycastPickTarget::default())
-                    .insert(HIGHLIGHT_TINT);
+            for (cylinder, half) in cylinders {
+                let mut cylinder = commands.spawn(cylinder);
+                if let Some(half) = half {
+                    cylinder.insert(half);
+                }
             }
+        });
+}
 
-            // add chemical bonds
-            for (i, j, b) in mol.bonds() {
-                let ai = mol.get_atom_unchecked(i);
-                let aj = mol.get_atom_unchecked(j);
-                let atom1 = Atom::new(ai);
-                let atom2 = Atom::new(aj);
-                let mut bond = Bond::new(atom1, atom2);
-                bond.set_visible(visible);
-                commands
-                    .spawn(BondBundle::new(bond, &mut meshes, &mut materials))
-                    .insert(frame_name);
+/// Periodic image of an atom displayed outside the unit cell, indexed
+/// in `PeriodicDisplay`
+#[derive(Clone, Copy, Debug, Component)]
+pub struct ImageAtom(pub usize);
+
+/// Bond drawn in periodic display, indexed in `PeriodicDisplay`
+#[derive(Clone, Copy, Debug, Component)]
+pub struct PeriodicBond(pub usize);
+
+/// Bond drawn in half from the atom of serial number towards its
+/// periodic image not displayed
+#[derive(Clone, Copy, Debug, Component)]
+pub struct BondStub(pub usize);
+
+/// An atom or its periodic image: the serial number and the image shift
+/// in cell vectors
+type ImageKey = (usize, [i32; 3]);
+
+/// A bond drawn between two displayed ends, or drawn in half from the
+/// first end if the other one is not displayed
+#[derive(Clone, Debug)]
+struct BondSegment {
+    /// Index in bonds of `PeriodicDisplay`
+    bond: usize,
+    ends: (ImageKey, ImageKey),
+    half: bool,
+    transform: Transform,
+}
+
+impl BondSegment {
+    /// Return true if `other` is drawn between the same ends.
+    fn same_ends(&self, other: &Self) -> bool {
+        self.bond == other.bond && self.ends == other.ends && self.half == other.half
+    }
+}
+
+/// Image atoms and bonds in periodic display of a molecule. Bonds in
+/// minimum image convention are found once for a topology, and the
+/// displayed entities are updated in place as long as the same images
+/// are displayed.
+#[derive(Clone, Debug, Default, Component)]
+pub struct PeriodicDisplay {
+    /// Bonded atoms in minimum image convention, with bond kinds and the
+    /// neighboring atom for placing multiple bonds
+    bonds: Vec<(usize, usize, gchemol::BondKind, Option<usize>)>,
+    /// Displayed image atoms with their positions
+    images: Vec<(ImageKey, Vec3)>,
+    segments: Vec<BondSegment>,
+}
+
+impl PeriodicDisplay {
+    /// Find bonds of `mol` in minimum image convention. Bond kinds
+    /// defined in `mol` are kept.
+    pub fn new(mol: &gchemol::Molecule) -> Self {
+        let mut pbc_mol = mol.clone();
+        pbc_mol.rebond();
+        for (i, j, b) in mol.bonds() {
+            if let Some(bond) = pbc_mol.get_bond_mut(i, j) {
+                *bond = b.clone();
             }
+        }
+        let bonds = pbc_mol
+            .bonds()
+            .map(|(i, j, b)| (i, j, b.kind(), find_plane_atom(&pbc_mol, i, j)))
+            .collect();
+        Self { bonds, ..default() }
+    }
+
+    /// Place image atoms within `margin` outside the unit cell of `lat`
+    /// in fractional coordinates, and bonds between them, for atom
+    /// positions in `mol`. Return true if the same images and bonds are
+    /// displayed as before, so the entities can be updated in place.
+    pub fn update(&mut self, mol: &gchemol::Molecule, lat: &gchemol::Lattice, margin: f64) -> bool {
+        use bevy::math::DVec3;
+        use std::collections::HashSet;
+
+        let to_frac = |p: DVec3| DVec3::from_array(lat.to_frac(p.to_array()).into());
+        let to_cart = |f: DVec3| DVec3::from_array(lat.to_cart(f.to_array()).into());
+        let apply_mic = |v: DVec3| DVec3::from_array(lat.apply_mic(v.to_array()).into());
 
-            // lattice
-            if let Some(lat) = mol.get_lattice() {
-                let vectors = create_lattice(lat, visible, meshes, materials);
-                for v in vectors {
-                    commands.spawn(v).insert(Lattice).insert(frame_name);
+        // include atoms on cell boundary
+        let margin = margin + 1e-4;
+        // the displayed positions of atoms and their images
+        let mut displayed: HashMap<ImageKey, DVec3> = HashMap::new();
+        let mut shifts_of: HashMap<usize, Vec<[i32; 3]>> = HashMap::new();
+        let mut images = vec![];
+        for (i, a) in mol.atoms() {
+            let f = to_frac(DVec3::from_array(a.position()));
+            let range = |x: f64| (-margin - x).ceil() as i32..=(1.0 + margin - x).floor() as i32;
+            let mut shifts = vec![[0; 3]];
+            for na in range(f.x) {
+                for nb in range(f.y) {
+                    for nc in range(f.z) {
+                        if [na, nb, nc] != [0; 3] {
+                            shifts.push([na, nb, nc]);
+                        }
+                    }
+                }
+            }
+            for &n in &shifts {
+                let pn = to_cart(f + DVec3::new(n[0] as f64, n[1] as f64, n[2] as f64));
+                displayed.insert((i, n), pn);
+                if n != [0; 3] {
+                    images.push(((i, n), pn.as_vec3()));
                 }
             }
+            shifts_of.insert(i, shifts);
+        }
+
+        let mut segments = vec![];
+        let mut drawn = HashSet::new();
+        for (k, &(i, j, _, plane)) in self.bonds.iter().enumerate() {
+            let pi = DVec3::from_array(mol.get_atom_unchecked(i).position());
+            let pj = DVec3::from_array(mol.get_atom_unchecked(j).position());
+            let dij = apply_mic(pj - pi);
+            // the image of atom `j` bonded with atom `i`
+            let s = (to_frac(pi + dij) - to_frac(pj)).round();
+            let s = [s.x as i32, s.y as i32, s.z as i32];
+            let plane = plane.map(|k| {
+                let pk = DVec3::from_array(mol.get_atom_unchecked(k).position());
+                apply_mic(pk - pi)
+            });
+
+            let ends = shifts_of[&i]
+                .iter()
+                .map(|&n| ((i, n), (j, [n[0] + s[0], n[1] + s[1], n[2] + s[2]]), dij))
+                .chain(
+                    shifts_of[&j]
+                        .iter()
+                        .map(|&m| ((j, m), (i, [m[0] - s[0], m[1] - s[1], m[2] - s[2]]), -dij)),
+                );
+            for (start, end, d) in ends {
+                let p0 = displayed[&start];
+                // the position of atom `i` or its image
+                let pi_image = if start.0 == i { p0 } else { p0 + d };
+                let pk = plane.map(|v| (pi_image + v).as_vec3());
+                if displayed.contains_key(&end) {
+                    let key = if start < end { (start, end) } else { (end, start) };
+                    if drawn.insert(key) {
+                        let (first, second) = if start.0 == i { (start, end) } else { (end, start) };
+                        let transform = bond_transform_at(displayed[&first].as_vec3(), displayed[&second].as_vec3(), pk);
+                        segments.push(BondSegment {
+                            bond: k,
+                            ends: (first, second),
+                            half: false,
+                            transform,
+                        });
+                    }
+                } else {
+                    // draw half bond towards the image not displayed
+                    let transform = bond_transform_at(p0.as_vec3(), (p0 + d / 2.0).as_vec3(), pk);
+                    segments.push(BondSegment {
+                        bond: k,
+                        ends: (start, end),
+                        half: true,
+                        transform,
+                    });
+                }
+            }
+        }
+
+        let same = self.images.len() == images.len()
+            && self.images.iter().zip(&images).all(|(a, b)| a.0 == b.0)
+            && self.segments.len() == segments.len()
+            && self.segments.iter().zip(&segments).all(|(a, b)| a.same_ends(b));
+        self.images = images;
+        self.segments = segments;
+        same
+    }
+
+    /// Return the position of displayed `image` atom.
+    pub fn image_position(&self, image: &ImageAtom) -> Vec3 {
+        self.images[image.0].1
+    }
+
+    /// Return the transform of displayed periodic `bond`.
+    pub fn bond_transform(&self, bond: &PeriodicBond) -> Transform {
+        self.segments[bond.0].transform
+    }
+
+    /// Spawn image atoms and bonds placed in last update.
+    pub fn spawn(
+        &self,
+        mol: &gchemol::Molecule,
+        repr: &RepresentationSettings,
+        commands: &mut ChildBuilder,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        cache: &mut AssetCache,
+    ) {
+        let atom = |i: usize| Atom::new(mol.get_atom_unchecked(i), repr.get(i), repr.styles());
+        for (k, &((i, _), p)) in self.images.iter().enumerate() {
+            commands
+                .spawn(AtomBundle::new(atom(i).with_position(p), meshes, materials, cache))
+                .insert(ImageAtom(k));
+        }
+        for (k, segment) in self.segments.iter().enumerate() {
+            let (i, j, kind, _) = self.bonds[segment.bond];
+            if segment.half {
+                let start = segment.ends.0 .0;
+                let bond = Bond::new(atom(start), atom(start))
+                    .with_kind(kind)
+                    .with_half_bonds(repr.half_bonds());
+                let index = (BondStub(start), PeriodicBond(k));
+                spawn_bond(bond, segment.transform, index, commands, meshes, materials, cache);
+            } else {
+                let bond = Bond::new(atom(i), atom(j)).with_kind(kind).with_half_bonds(repr.half_bonds());
+                let index = (BondIndex(i, j), PeriodicBond(k));
+                spawn_bond(bond, segment.transform, index, commands, meshes, materials, cache);
+            }
+        }
+    }
+}
+
+pub fn spawn_lattice(
+    mol: &gchemol::Molecule,
+    commands: &mut ChildBuilder,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
+) {
+    if let Some(lat) = mol.get_lattice() {
+        let vectors = create_lattice(lat, true, meshes, materials, cache);
+        for v in vectors {
+            commands.spawn(v).insert(Lattice);
+        }
+    }
+}
+
+/// Update the transform of bond in `transform` using new atom
+/// positions in `mol`.
+pub fn update_bond_transform(mol: &gchemol::Molecule, bond: &BondIndex, transform: &mut Transform) {
+    *transform = bond_transform(mol, bond.0, bond.1);
+}
+
+pub fn spawn_molecule(
+    mol: &gchemol::Molecule,
+    repr: &RepresentationSettings,
+    frame_index: usize,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    cache: &mut AssetCache,
+) {
+    let periodic = mol.get_lattice().filter(|_| repr.is_periodic(mol)).map(|lat| {
+        let mut periodic = PeriodicDisplay::new(mol);
+        periodic.update(mol, lat, repr.periodic().unwrap_or_default());
+        periodic
+    });
+    let polyhedra = PolyhedraDisplay::new(mol, repr);
+    let mut entity = commands.spawn(SpatialBundle::default());
+    entity
+        .insert(Molecule)
+        .insert(Topology::new(mol))
+        // for animation control
+        .insert(FrameIndex(frame_index))
+        .with_children(|commands| {
+            spawn_atoms(mol, repr, commands, meshes, materials, cache);
+            // add chemical bonds
+            spawn_bonds(mol, repr, commands, meshes, materials, cache);
+            if let Some(periodic) = &periodic {
+                periodic.spawn(mol, repr, commands, meshes, materials, cache);
+            }
+            spawn_lattice(mol, commands, meshes, materials, cache);
+            polyhedra.spawn(mol, repr, commands, meshes, materials, cache);
         });
+    if let Some(periodic) = periodic {
+        entity.insert(periodic);
+    }
+    if !polyhedra.is_empty() {
+        entity.insert(polyhedra);
+    }
 }
 // d5c13162 ends here
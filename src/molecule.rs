@@ -8,7 +8,7 @@ use bevy_mod_picking::prelude::*;
 // a83ae206 ends here
 
 // [[file:../bevy.note::031857dd][031857dd]]
-use crate::base::FrameIndex;
+use crate::base::{FrameIndex, PeriodicDisplay, PolyhedraDisplay, Topology};
 // 031857dd ends here
 
 // [[file:../bevy.note::711fbcb5][711fbcb5]]
@@ -125,6 +125,30 @@ impl MoleculeTrajectory {
         let index = self.get_current_frame_index(current_frame)?;
         self.mols.get_mut(index)
     }
+
+    /// Replace the molecule in frame `index` with `mol`. Return the
+    /// replaced one.
+    pub fn replace_frame(&mut self, index: usize, mol: Molecule) -> Option<Molecule> {
+        let old = self.mols.get_mut(index)?;
+        Some(std::mem::replace(old, mol))
+    }
+
+    /// Insert `mol` as frame `index`, or as the last frame if `index`
+    /// is out of range.
+    pub fn insert_frame(&mut self, index: usize, mol: Molecule) {
+        let index = index.min(self.mols.len());
+        self.mols.insert(index, mol);
+    }
+
+    /// Remove frame `index`. Return the removed molecule.
+    pub fn remove_frame(&mut self, index: usize) -> Option<Molecule> {
+        (index < self.mols.len()).then(|| self.mols.remove(index))
+    }
+
+    /// Replace all frames with `mols`. Return the replaced ones.
+    pub fn replace_frames(&mut self, mols: Vec<Molecule>) -> Vec<Molecule> {
+        std::mem::replace(&mut self.mols, mols)
+    }
 }
 // c068ff9c ends here
 
@@ -137,20 +161,154 @@ fn keyboard_animation_control(keyboard_input: Res<Input<KeyCode>>, mut current_f
     }
 }
 
+/// The displayed molecule with entities for reuse
+type DisplayedMolecule = (
+    Entity,
+    &'static mut Topology,
+    &'static mut FrameIndex,
+    &'static Children,
+    Option<&'static mut PeriodicDisplay>,
+    Option<&'static PolyhedraDisplay>,
+);
+
+/// The displayed atoms, bonds, periodic images, lattice and polyhedra
+/// to be moved or kept
+type DisplayedChild = (
+    Option<&'static AtomIndex>,
+    Option<&'static crate::base::BondIndex>,
+    Option<&'static crate::base::ImageAtom>,
+    Option<&'static crate::base::PeriodicBond>,
+    Option<&'static crate::base::Lattice>,
+    Option<(&'static crate::base::Polyhedron, &'static Handle<Mesh>)>,
+    &'static mut Transform,
+);
+
+/// Display current frame of the trajectory. Atom entities are reused
+/// if current frame has the same atoms as the displayed one, and bonds
+/// or lattice are recreated only if they change. In periodic display,
+/// image atoms and bonds are respawned only if different images are
+/// displayed. Meshes of coordination polyhedra are updated in place.
 fn traj_animation_player(
+    mut commands: Commands,
     traj: Res<MoleculeTrajectory>,
     current_frame: Res<CurrentFrame>,
-    mut visibility_query: Query<(&mut Visibility, &FrameIndex)>,
+    mut assets: crate::base::MoleculeAssets,
+    repr: Res<crate::base::RepresentationSettings>,
+    mut molecule_query: Query<DisplayedMolecule>,
+    mut children_query: Query<DisplayedChild>,
 ) {
-    if let Some(ci) = traj.get_current_frame_index(&current_frame) {
-        for (mut visibility, FrameIndex(fi)) in visibility_query.iter_mut() {
-            if *fi == ci as usize {
-                *visibility = Visibility::Visible;
-            } else {
-                *visibility = Visibility::Hidden;
+    if !traj.is_changed() && !current_frame.is_changed() && !repr.is_changed() {
+        return;
+    }
+
+    let Some(ci) = traj.get_current_frame_index(&current_frame) else {
+        // no frame to display
+        for (entity, ..) in molecule_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    let mol = &traj.mols[ci];
+    let topology = Topology::new(mol);
+    let crate::base::MoleculeAssets { meshes, materials, cache } = &mut assets;
+
+    if let Ok((entity, mut displayed, mut frame_index, children, periodic, polyhedra)) = molecule_query.get_single_mut() {
+        // replace only changed parts when atoms remain the same
+        if displayed.same_atoms(&topology) && !repr.is_changed() {
+            let same_bonds = displayed.same_bonds(&topology);
+            let same_lattice = displayed.same_lattice(&topology);
+            // bonds in minimum image convention are found again only if
+            // connectivity changes
+            let lattice = mol.get_lattice().filter(|_| repr.is_periodic(mol));
+            let margin = repr.periodic().unwrap_or_default();
+            let mut periodic = periodic.filter(|_| same_bonds);
+            let same_images = match (lattice, periodic.as_deref_mut()) {
+                (Some(lat), Some(periodic)) => periodic.update(mol, lat, margin),
+                _ => false,
+            };
+            let new_periodic = match (lattice, &periodic) {
+                (Some(lat), None) => {
+                    let mut periodic = PeriodicDisplay::new(mol);
+                    periodic.update(mol, lat, margin);
+                    Some(periodic)
+                }
+                _ => None,
+            };
+            let reused = periodic.as_deref().filter(|_| same_images);
+            // neighbors of central atoms are found again only if
+            // connectivity changes
+            let polyhedra = polyhedra.filter(|_| same_bonds);
+            let new_polyhedra = polyhedra.is_none().then(|| PolyhedraDisplay::new(mol, &repr));
+            for &child in children.iter() {
+                match (children_query.get_mut(child), reused, polyhedra) {
+                    (Ok((Some(AtomIndex(i)), .., mut transform)), ..) => {
+                        let atom = mol.get_atom_unchecked(*i);
+                        transform.translation = crate::base::as_vec3(atom.position());
+                    }
+                    (Ok((.., Some((polyhedron, handle)), _)), _, Some(polyhedra)) => {
+                        if let Some(mesh) = meshes.get_mut(handle) {
+                            polyhedra.update_mesh(mol, polyhedron, mesh);
+                        }
+                    }
+                    (Ok((None, _, Some(image), _, _, _, mut transform)), Some(periodic), _) => {
+                        transform.translation = periodic.image_position(image);
+                    }
+                    (Ok((None, _, _, Some(bond), _, _, mut transform)), Some(periodic), _) => {
+                        *transform = periodic.bond_transform(bond);
+                    }
+                    (Ok((None, Some(bond), None, None, _, _, mut transform)), ..) if same_bonds => {
+                        crate::base::update_bond_transform(mol, bond, &mut transform);
+                    }
+                    (Ok((.., Some(_), _, _)), ..) if same_lattice => (),
+                    // outdated bonds, images, lattice and polyhedra
+                    _ => commands.entity(child).despawn_recursive(),
+                }
+            }
+            let respawned = new_periodic
+                .as_ref()
+                .or_else(|| periodic.as_deref().filter(|_| lattice.is_some() && !same_images));
+            commands.entity(entity).with_children(|commands| {
+                if !same_bonds {
+                    crate::base::spawn_bonds(mol, &repr, commands, meshes, materials, cache);
+                }
+                if let Some(periodic) = respawned {
+                    periodic.spawn(mol, &repr, commands, meshes, materials, cache);
+                }
+                if !same_lattice {
+                    crate::base::spawn_lattice(mol, commands, meshes, materials, cache);
+                }
+                if let Some(polyhedra) = &new_polyhedra {
+                    polyhedra.spawn(mol, &repr, commands, meshes, materials, cache);
+                }
+            });
+            match new_periodic {
+                Some(periodic) => {
+                    commands.entity(entity).insert(periodic);
+                }
+                None if lattice.is_none() => {
+                    commands.entity(entity).remove::<PeriodicDisplay>();
+                }
+                _ => (),
+            }
+            match new_polyhedra {
+                Some(polyhedra) if !polyhedra.is_empty() => {
+                    commands.entity(entity).insert(polyhedra);
+                }
+                Some(_) => {
+                    commands.entity(entity).remove::<PolyhedraDisplay>();
+                }
+                None => (),
             }
+            *displayed = topology;
+            frame_index.0 = ci;
+            return;
         }
     }
+
+    for (entity, ..) in molecule_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    crate::base::spawn_molecule(mol, &repr, ci, &mut commands, meshes, materials, cache);
 }
 // 20198b2d ends here
 
@@ -193,6 +351,58 @@ fn update_atom_selection(
         }
     }
 }
+
+/// Keep selection of atoms respawned, e.g. on changing freezing flags.
+/// The initial material is required for highlighting selection.
+fn restore_atom_selection(
+    selected_atoms: Res<SelectedAtoms>,
+    mut atoms_query: Query<
+        (&AtomIndex, &mut PickSelection),
+        Added<bevy_mod_picking::highlight::InitialHighlight<StandardMaterial>>,
+    >,
+) {
+    for (AtomIndex(i), mut selection) in atoms_query.iter_mut() {
+        if selected_atoms.0.contains(i) && !selection.is_selected {
+            selection.is_selected = true;
+        }
+    }
+}
+
+/// Make half bonds follow the selection highlight of atoms nearby.
+fn update_half_bond_highlight(
+    changed_atoms: Query<(), Changed<PickSelection>>,
+    atoms_query: Query<(&AtomIndex, &PickSelection)>,
+    mut half_bonds_query: Query<(&crate::base::HalfBond, &Parent, &mut Handle<StandardMaterial>)>,
+    added_half_bonds: Query<(), Added<crate::base::HalfBond>>,
+    bonds_query: Query<(Option<&crate::base::BondIndex>, Option<&crate::base::BondStub>)>,
+    mut assets: crate::base::MoleculeAssets,
+) {
+    if changed_atoms.is_empty() && added_half_bonds.is_empty() {
+        return;
+    }
+
+    let selected: std::collections::HashSet<_> = atoms_query
+        .iter()
+        .filter_map(|(AtomIndex(i), selection)| selection.is_selected.then_some(*i))
+        .collect();
+    for (half, parent, mut material) in half_bonds_query.iter_mut() {
+        let atom = match bonds_query.get(parent.get()) {
+            Ok((Some(bond), _)) => Some(if half.first { bond.0 } else { bond.1 }),
+            Ok((None, Some(stub))) => Some(stub.0),
+            _ => None,
+        };
+        if let Some(atom) = atom {
+            let handle = if selected.contains(&atom) {
+                assets.cache.selected_material(half.color, &mut assets.materials)
+            } else {
+                assets.cache.material(half.color, &mut assets.materials)
+            };
+            if *material != handle {
+                *material = handle;
+            }
+        }
+    }
+}
 // 31795e08 ends here
 
 // [[file:../bevy.note::92f358a8][92f358a8]]
@@ -263,8 +473,6 @@ use crate::base::CurrentFrame;
 
 pub fn spawn_molecules(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut selected_atoms: ResMut<SelectedAtoms>,
     traj: Res<MoleculeTrajectory>,
 ) {
@@ -288,29 +496,65 @@ pub fn spawn_molecules(
 
     // mouse: zoom, rotate and translate
     commands
-        .spawn(Camera3dBundle {
-            // projection: Projection::Orthographic(OrthographicProjection {
-            //     near: -500.0,
-            //     far: 500.0,
-            //     ..default()
-            // }),
-            ..default()
-        })
+        // the projection can be switched in `switch_camera_projection`
+        .spawn(Camera3dBundle::default())
         .insert(arcball_camera)
         .insert(RaycastPickCamera::default());
 
-    // create atoms and bonds
-    for (fi, mol) in traj.mols.iter().enumerate() {
-        // only show the first frame
-        let visible = fi == 0;
-        crate::base::spawn_molecule(mol, visible, fi, &mut commands, &mut meshes, &mut materials);
-    }
+    // atoms and bonds will be created in `traj_animation_player`
 
     // clear selection
     selected_atoms.0.clear();
 }
 // 1c6c0570 ends here
 
+// [[file:../bevy.note::e03b7c59][e03b7c59]]
+/// Projection mode of the camera
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum ProjectionMode {
+    #[default]
+    Perspective,
+    Orthographic,
+}
+
+/// Create orthographic projection with the same apparent size as
+/// `perspective` for objects at the focus of camera, which is
+/// `radius` away.
+fn orthographic_projection(perspective: &PerspectiveProjection, radius: f32) -> OrthographicProjection {
+    use bevy::render::camera::ScalingMode;
+
+    // the view height at distance 1.0, so that the scale can be kept in
+    // sync with the orbit radius when zooming
+    let height = 2.0 * (perspective.fov * 0.5).tan();
+    OrthographicProjection {
+        near: -500.0,
+        far: 500.0,
+        scaling_mode: ScalingMode::FixedVertical(height),
+        scale: radius,
+        ..default()
+    }
+}
+
+/// Switch camera projection on `ProjectionMode` change.
+fn switch_camera_projection(mode: Res<ProjectionMode>, mut camera_query: Query<(&PanOrbitCamera, &mut Projection)>) {
+    if !mode.is_changed() {
+        return;
+    }
+    for (pan_orbit, mut projection) in camera_query.iter_mut() {
+        match (*mode, &*projection) {
+            (ProjectionMode::Orthographic, Projection::Perspective(p)) => {
+                *projection = Projection::Orthographic(orthographic_projection(p, pan_orbit.radius));
+            }
+            (ProjectionMode::Perspective, Projection::Orthographic(_)) => {
+                // the orbit radius is kept in sync with orthographic scale
+                *projection = Projection::Perspective(PerspectiveProjection::default());
+            }
+            _ => {}
+        }
+    }
+}
+// e03b7c59 ends here
+
 // [[file:../bevy.note::8ec82258][8ec82258]]
 #[derive(Debug, Clone)]
 pub struct MoleculePlugin {
@@ -331,12 +575,18 @@ impl Plugin for MoleculePlugin {
         app.insert_resource(self.traj.clone())
             .insert_resource(CurrentFrame::default())
             .insert_resource(SelectedAtoms::default())
+            .init_resource::<ProjectionMode>()
+            .init_resource::<crate::base::AssetCache>()
+            .insert_resource(crate::base::RepresentationSettings::default().with_styles(crate::base::ElementStyles::load()))
             .add_plugin(crate::animation::AnimationPlugin)
             .add_startup_system(spawn_molecules)
             .add_system(update_light_with_camera)
+            .add_system(switch_camera_projection)
             .add_system(keyboard_animation_control)
             .add_system(drag_and_drop_files)
             .add_system(update_atom_selection)
+            .add_system(restore_atom_selection)
+            .add_system(update_half_bond_highlight)
             .add_system(traj_animation_player);
     }
 }
@@ -12,17 +12,12 @@ pub struct RemoteConsolePlugin;
 // d66e839e ends here
 
 // [[file:../../bevy.note::22cddf8a][22cddf8a]]
-fn delete_command(
-    mut commands: Commands,
-    molecule_query: Query<Entity, With<crate::base::Molecule>>,
-    mut reader: EventReader<StreamEvent>,
-) {
+fn delete_command(mut traj: ResMut<crate::molecule::MoleculeTrajectory>, mut reader: EventReader<StreamEvent>) {
     for (_per_frame, StreamEvent(cmd)) in reader.iter().enumerate() {
         if let RemoteCommand::Delete = cmd {
-            if let Ok(molecule_entity) = molecule_query.get_single() {
-                info!("remove molecule");
-                commands.entity(molecule_entity).despawn_recursive();
-            }
+            info!("remove molecule");
+            // displayed molecule will be removed in `traj_animation_player`
+            *traj = crate::molecule::MoleculeTrajectory::default();
         }
     }
 }
@@ -32,14 +27,12 @@ fn delete_command(
 use crate::arcball::PanOrbitCamera;
 
 fn load_command(
-    mut commands: Commands,
     mut reader: EventReader<StreamEvent>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    molecule_query: Query<Entity, With<crate::base::Molecule>>,
+    mut selection_query: Query<&mut bevy_mod_picking::prelude::PickSelection>,
     mut arcball_camera: Query<&mut PanOrbitCamera>,
     mut traj: ResMut<crate::molecule::MoleculeTrajectory>,
     mut selected_atoms: ResMut<crate::molecule::SelectedAtoms>,
+    mut representation: ResMut<crate::base::RepresentationSettings>,
 ) {
     for (_per_frame, StreamEvent(cmd)) in reader.iter().enumerate() {
         match cmd {
@@ -47,30 +40,24 @@ fn load_command(
                 if !mols.is_empty() {
                     let mol = &mols[0];
                     info!("handle received mol: {}", mol.title());
-                    // remove existing molecule
-                    if let Ok(molecule_entity) = molecule_query.get_single() {
-                        info!("molecule removed");
-                        commands.entity(molecule_entity).despawn_recursive();
-                    }
-                    // show molecule on received
-                    // create atoms and bonds
-                    for (fi, mol) in mols.iter().enumerate() {
-                        // only show the first frame
-                        let visible = fi == 0;
-                        crate::base::spawn_molecule(mol, visible, fi, &mut commands, &mut meshes, &mut materials);
-                    }
                     // recenter view
                     if let Ok(mut pan_orbit) = arcball_camera.get_single_mut() {
                         let center = mol.center_of_geometry().map(|x| x as f32);
                         pan_orbit.focus = center.into();
                     }
-                    // also update trajecotry resource
+                    // representations of individual atoms are meaningless for new molecules
+                    representation.clear_atoms();
+                    // show molecule on received: atoms and bonds will be
+                    // updated in `traj_animation_player`
                     *traj = crate::molecule::MoleculeTrajectory::new(mols.to_vec());
                 } else {
                     warn!("Received empty molecule list.");
                 }
                 // clear selection on loading
                 selected_atoms.0.clear();
+                for mut selection in selection_query.iter_mut() {
+                    selection.is_selected = false;
+                }
                 break;
             }
             _ => {
@@ -30,13 +30,29 @@ macro_rules! show_combo_box_enum {
 // 45bd6a9d ends here
 
 // [[file:../bevy.note::8d1285a1][8d1285a1]]
+mod camera_view;
 mod compute;
+mod constraints;
 mod cp2k;
+mod edit_atoms;
+mod element_styles;
 mod gaussian;
+mod history;
+mod lattice_plane;
+mod measurement;
+mod monitor;
+mod move_atoms;
 mod orca;
+mod orientation;
+mod saved_views;
 mod selection;
+mod slab;
+mod supercell;
+mod symmetry;
 mod template;
+mod unit_cell;
 mod vasp;
+mod wrap_atoms;
 // 8d1285a1 ends here
 
 // [[file:../bevy.note::02f2343f][02f2343f]]
@@ -67,8 +83,24 @@ impl AtomLabel {
 #[derive(Debug, Resource)]
 pub struct UiState {
     label_atoms_checked: bool,
+    move_atoms_enabled: bool,
     message: String,
     periodic_table_window_open: bool,
+    element_styles_window_open: bool,
+    constraints_window_open: bool,
+    lattice_plane_window_open: bool,
+    unit_cell_window_open: bool,
+    supercell_window_open: bool,
+    slab_window_open: bool,
+    symmetry_window_open: bool,
+    saved_views_window_open: bool,
+    monitor_panel_open: bool,
+    orientation_axes_visible: bool,
+    lattice_labels_visible: bool,
+    // keep molecules whole on wrapping atoms
+    wrap_keep_molecules: bool,
+    // lattice direction for camera view
+    view_uvw: [i32; 3],
     // atom selection
     atom_selection: selection::State,
 }
@@ -77,8 +109,22 @@ impl Default for UiState {
     fn default() -> Self {
         Self {
             label_atoms_checked: false,
+            move_atoms_enabled: false,
             message: "Tip: You can press `q` to exit.".to_owned(),
             periodic_table_window_open: false,
+            element_styles_window_open: false,
+            constraints_window_open: false,
+            lattice_plane_window_open: false,
+            unit_cell_window_open: false,
+            supercell_window_open: false,
+            slab_window_open: false,
+            symmetry_window_open: false,
+            saved_views_window_open: false,
+            monitor_panel_open: false,
+            orientation_axes_visible: true,
+            lattice_labels_visible: false,
+            wrap_keep_molecules: false,
+            view_uvw: [1, 1, 1],
             atom_selection: selection::State::default(),
         }
     }
@@ -153,6 +199,10 @@ fn update_atom_labels_with_camera(
                         // A hack to hide the text when the it's behind the camera
                         style.position.bottom = Val::Px(-1000.0);
                     }
+                } else {
+                    // the atom has been removed, e.g. by switching to
+                    // another molecule
+                    style.position.bottom = Val::Px(-1000.0);
                 }
             }
         }
@@ -173,7 +223,7 @@ fn handle_atom_label_events(
     asset_server: Res<AssetServer>,
     mut events: EventReader<AtomLabelEvent>,
     label_query: Query<Entity, With<AtomLabel>>,
-    frame_query: Query<(Entity, &crate::base::FrameIndex, &Visibility), With<crate::base::Atom>>,
+    visibility_query: Query<&Visibility, With<crate::base::Atom>>,
 ) {
     for event in events.iter() {
         match event {
@@ -182,10 +232,11 @@ fn handle_atom_label_events(
                 // NOTE: visibility hierarchy not work here
                 // let child = commands.spawn((label, AtomLabel::new(*entity))).id();
                 // commands.entity(*entity).add_child(child);
-                let (_, iframe, vis) = frame_query.iter().find(|part| part.0 == *entity).unwrap();
-                if vis != Visibility::Hidden {
-                    let label = create_label_text(&asset_server, text, true);
-                    commands.spawn((label, AtomLabel::new(*entity))).insert(*iframe);
+                if let Ok(vis) = visibility_query.get(*entity) {
+                    if vis != Visibility::Hidden {
+                        let label = create_label_text(&asset_server, text, true);
+                        commands.spawn((label, AtomLabel::new(*entity)));
+                    }
                 }
             }
             AtomLabelEvent::Delete => {
@@ -242,7 +293,7 @@ impl UiApp {
             .add_filter("*.xsd", &["xsd"])
             .add_filter("*.cjson", &["cjson"])
             .add_filter("Gaussian (*.com, *.gjf)", &["com", "gjf"])
-            .add_filter("VASP (*.vasp)", &["vasp"])
+            .add_filter("VASP (*.vasp, *.poscar)", &["vasp", "poscar"])
             .pick_file()
         {
             if let Ok(mols) = gchemol::io::read(path) {
@@ -266,8 +317,24 @@ impl UiApp {
         }
     }
 
-    fn save_trajectory(&mut self, traj: ResMut<crate::molecule::MoleculeTrajectory>, mut state: ResMut<UiState>) {
+    fn save_trajectory(
+        &mut self,
+        traj: ResMut<crate::molecule::MoleculeTrajectory>,
+        current_frame: &crate::base::CurrentFrame,
+        mut state: ResMut<UiState>,
+    ) {
         if let Some(path) = rfd::FileDialog::new().save_file() {
+            // POSCAR holds a single periodic structure, with freezing
+            // flags written as selective dynamics
+            if gchemol::io::guess_format_from_path(&path).as_deref() == Some("vasp/input") {
+                let mol = traj.get_current_molecule(current_frame).filter(|mol| mol.lattice.is_some());
+                state.message = match mol.map(|mol| gchemol::io::write(&path, [mol])) {
+                    Some(Ok(_)) => format!("Current molecule saved to {path:?}"),
+                    Some(Err(err)) => format!("{err:?}"),
+                    None => "POSCAR requires a molecule with lattice".into(),
+                };
+                return;
+            }
             traj.save_as(path.as_ref());
             state.message = format!("Molecules saved to {path:?}");
         }
@@ -302,14 +369,16 @@ fn center_molecule(
 impl UiApp {
     fn clear_molecules(
         &mut self,
-        mut commands: Commands,
+        mut traj: ResMut<crate::molecule::MoleculeTrajectory>,
         mut state: ResMut<UiState>,
         mut label_events: EventWriter<AtomLabelEvent>,
-        molecule_query: Query<Entity, With<crate::base::Molecule>>,
+        history: &mut history::History,
     ) {
-        if let Ok(molecule_entity) = molecule_query.get_single() {
+        if traj.nframes() > 0 {
             info!("remove molecule");
-            commands.entity(molecule_entity).despawn_recursive();
+            history.record_trajectory("Clear molecules", &traj);
+            // displayed molecule will be removed in `traj_animation_player`
+            *traj = crate::molecule::MoleculeTrajectory::default();
             // also remove atom labels
             label_events.send(AtomLabelEvent::Delete);
         } else {
@@ -358,16 +427,26 @@ mod panel {
     use crate::ui::AtomLabelEvent;
 
     use crate::arcball::PanOrbitCamera;
+    use crate::molecule::ProjectionMode;
     use bevy::app::AppExit;
     use bevy::prelude::*;
     use bevy_egui::{egui, EguiContexts};
     use bevy_mod_picking::prelude::PickSelection;
 
+    /// Resources and events for viewing and editing in side panels
+    #[derive(bevy::ecs::system::SystemParam)]
+    pub struct EditingParams<'w> {
+        projection_mode: ResMut<'w, ProjectionMode>,
+        view_events: EventWriter<'w, super::camera_view::CameraViewEvent>,
+        measurements: ResMut<'w, super::measurement::State>,
+        history: ResMut<'w, super::history::History>,
+        history_events: EventWriter<'w, super::history::HistoryEvent>,
+    }
+
     pub fn side_panels(
         mut state: ResMut<UiState>,
         mut contexts: EguiContexts,
         mut commands: Commands,
-        molecule_query: Query<Entity, With<crate::base::Molecule>>,
         label_events: EventWriter<AtomLabelEvent>,
         atoms_query: Query<(Entity, &AtomIndex, &crate::base::Atom)>,
         mut traj: ResMut<crate::molecule::MoleculeTrajectory>,
@@ -378,7 +457,16 @@ mod panel {
         selected_atoms: Res<crate::molecule::SelectedAtoms>,
         mut clipboard: ResMut<bevy_egui::EguiClipboard>,
         mut arcball_camera: Query<&mut PanOrbitCamera>,
+        mut representation: ResMut<crate::base::RepresentationSettings>,
+        editing: EditingParams,
     ) {
+        let EditingParams {
+            mut projection_mode,
+            mut view_events,
+            mut measurements,
+            mut history,
+            mut history_events,
+        } = editing;
         let ctx = contexts.ctx_mut();
 
         // use light theme
@@ -390,45 +478,10 @@ mod panel {
         let mut app = UiApp::default();
 
         // measure distance, angle, torsion for selected atoms
-        match selected_atoms.0.len() {
-            n @ 2..=4 => {
-                let atoms_s: Vec<_> = selected_atoms.0.iter().map(|x| x.to_string()).collect();
-                let atoms = atoms_s.join("-");
-                let current_molecule = traj.get_current_molecule(&current_frame).expect("no active mol");
-                let i = selected_atoms.0[0];
-                let j = selected_atoms.0[1];
-                let message = match n {
-                    2 => {
-                        if let Some(v) = current_molecule.get_distance(i, j) {
-                            format!("distance({atoms}) = {v:6.4} Å")
-                        } else {
-                            format!("{atoms}")
-                        }
-                    }
-                    3 => {
-                        let k = selected_atoms.0[2];
-                        if let Some(v) = current_molecule.get_angle(i, j, k) {
-                            let v = v.to_degrees();
-                            format!("angle({atoms}) = {v:6.2} °")
-                        } else {
-                            format!("{atoms}")
-                        }
-                    }
-                    4 => {
-                        let k = selected_atoms.0[2];
-                        let l = selected_atoms.0[3];
-                        if let Some(v) = current_molecule.get_torsion(i, j, k, l) {
-                            let v = v.to_degrees();
-                            format!("torsion({atoms}) = {v:6.2} °")
-                        } else {
-                            format!("{atoms}")
-                        }
-                    }
-                    _ => unreachable!(),
-                };
-                state.message = message;
+        if let Some(m) = super::measurement::Measurement::from_atoms(&selected_atoms.0) {
+            if let Some(mol) = traj.get_current_molecule(&current_frame) {
+                state.message = m.format(mol);
             }
-            _ => {}
         }
 
         egui::TopBottomPanel::top("top_panel").resizable(true).show(ctx, |ui| {
@@ -470,24 +523,61 @@ mod panel {
                     }
                 });
                 ui.menu_button("Edit", |ui| {
+                    use super::history::HistoryEvent;
+
+                    let undo = history.next_undo().map(|s| format!("Undo {s}"));
+                    if ui
+                        .add_enabled(undo.is_some(), egui::Button::new(undo.as_deref().unwrap_or("Undo")))
+                        .on_hover_text("Ctrl+Z")
+                        .clicked()
+                    {
+                        history_events.send(HistoryEvent::Undo);
+                        ui.close_menu();
+                    }
+                    let redo = history.next_redo().map(|s| format!("Redo {s}"));
+                    if ui
+                        .add_enabled(redo.is_some(), egui::Button::new(redo.as_deref().unwrap_or("Redo")))
+                        .on_hover_text("Ctrl+Shift+Z")
+                        .clicked()
+                    {
+                        history_events.send(HistoryEvent::Redo);
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     // Remove all molecules
                     if ui.button("Clear Molecule").clicked() {
                         action = Action::Clear;
                         ui.close_menu();
                     }
-                    if ui.button("Freeze selected atoms").clicked() {
-                        if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+                    if ui.button("Delete current frame").clicked() {
+                        if let Some(i) = traj.get_current_frame_index(&current_frame) {
+                            history.remove_frame(&mut traj, i);
+                            state.message = format!("Frame {i} deleted");
+                        }
+                        ui.close_menu();
+                    }
+                    for (label, freezing) in [("Freeze selected atoms", [true; 3]), ("Unfreeze selected atoms", [false; 3])] {
+                        if ui.button(label).clicked() {
                             let selected_atoms = crate::molecule::get_selected_atoms(&selection_query);
                             if !selected_atoms.is_empty() {
-                                for i in selected_atoms {
-                                    if let Some(a) = mol.get_atom_mut(i) {
-                                        a.set_freezing([true; 3]);
-                                    }
+                                history.record_current_frame(label, &traj, &current_frame);
+                            }
+                            if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+                                if !selected_atoms.is_empty() {
+                                    super::constraints::set_freezing(mol, &selected_atoms, freezing);
+                                    ui.close_menu();
                                 }
-                                ui.close_menu();
                             }
                         }
                     }
+                    if ui
+                        .button("Constraints…")
+                        .on_hover_text("Freeze selected atoms in x, y or z")
+                        .clicked()
+                    {
+                        state.constraints_window_open = true;
+                        ui.close_menu();
+                    }
                 });
                 ui.menu_button("View", |ui| {
                     if ui
@@ -498,19 +588,143 @@ mod panel {
                         super::center_molecule(arcball_camera, &traj, &current_frame, &selected_atoms.0);
                         ui.close_menu();
                     }
+                    ui.menu_button("View along", |ui| {
+                        super::camera_view::show_menu(ui, &mut state.view_uvw, &mut view_events);
+                    });
+                    if ui.button("Saved views…").on_hover_text("Save and restore named camera views").clicked() {
+                        state.saved_views_window_open = true;
+                        ui.close_menu();
+                    }
+                    let mut orthographic = *projection_mode == ProjectionMode::Orthographic;
+                    if ui
+                        .checkbox(&mut orthographic, "Orthographic")
+                        .on_hover_text("Switch between orthographic and perspective projection")
+                        .changed()
+                    {
+                        *projection_mode = if orthographic {
+                            ProjectionMode::Orthographic
+                        } else {
+                            ProjectionMode::Perspective
+                        };
+                    }
+                    ui.checkbox(&mut state.orientation_axes_visible, "Orientation axes")
+                        .on_hover_text("Show axes rotating with the camera in the corner, or cell vectors for crystals");
+                    ui.checkbox(&mut state.lattice_labels_visible, "Lattice labels")
+                        .on_hover_text("Label cell vectors a, b, c and the origin O");
+                    ui.menu_button("Representation", |ui| {
+                        use crate::base::Representation;
+
+                        let global = representation.get_global();
+                        for repr in enum_iterator::all::<Representation>() {
+                            if ui.radio(global == Some(repr), repr.name()).clicked() {
+                                representation.set_global(repr);
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        let mut half_bonds = representation.half_bonds();
+                        if ui
+                            .checkbox(&mut half_bonds, "Two-color bonds")
+                            .on_hover_text("Draw bonds in two halves colored by bonded atoms")
+                            .changed()
+                        {
+                            representation.set_half_bonds(half_bonds);
+                        }
+                        let mut periodic = representation.periodic().is_some();
+                        let mut margin = representation.periodic().unwrap_or(0.0);
+                        ui.horizontal(|ui| {
+                            let changed = ui
+                                .checkbox(&mut periodic, "Periodic images")
+                                .on_hover_text("Display image atoms and bonds crossing cell boundary for crystals")
+                                .changed();
+                            let changed = ui
+                                .add_enabled(periodic, egui::DragValue::new(&mut margin).speed(0.01).clamp_range(0.0..=0.5))
+                                .on_hover_text("Display image atoms within this margin outside the unit cell in fractional coordinates")
+                                .changed()
+                                || changed;
+                            if changed {
+                                representation.set_periodic(periodic.then_some(margin));
+                            }
+                        });
+                        ui.menu_button("Polyhedra", |ui| {
+                            // elements in current molecule
+                            let symbols: std::collections::BTreeSet<_> = traj
+                                .get_current_molecule(&current_frame)
+                                .map(|mol| mol.symbols().map(|x| x.to_owned()).collect())
+                                .unwrap_or_default();
+                            if symbols.is_empty() {
+                                ui.label("No molecule present");
+                            }
+                            for symbol in symbols {
+                                let mut enabled = representation.has_polyhedra(&symbol);
+                                if ui
+                                    .checkbox(&mut enabled, &symbol)
+                                    .on_hover_text("Draw coordination polyhedra around atoms of this element")
+                                    .changed()
+                                {
+                                    representation.set_polyhedra(&symbol, enabled);
+                                }
+                            }
+                        });
+                        if ui
+                            .button("Element styles…")
+                            .on_hover_text("Edit colors and radii of elements")
+                            .clicked()
+                        {
+                            state.element_styles_window_open = true;
+                            ui.close_menu();
+                        }
+                        ui.menu_button("Selected atoms", |ui| {
+                            for repr in enum_iterator::all::<Representation>() {
+                                if ui.button(repr.name()).clicked() {
+                                    let selected_atoms = crate::molecule::get_selected_atoms(&selection_query);
+                                    if selected_atoms.is_empty() {
+                                        state.message = "No atoms selected".into();
+                                    } else {
+                                        representation.set_atoms(&selected_atoms, repr);
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
                 });
                 ui.menu_button("Crystal", |ui| {
                     if ui.button("Unbuild crystal").clicked() {
                         action = Action::UnbuildCrystal;
                     }
                     if ui.button("Edit unit cell…").clicked() {
-                        state.message = "no implemented yet".into();
-                    }
-                    if ui.button("Wrap atoms to unit cell").clicked() {
-                        state.message = "no implemented yet".into();
+                        state.unit_cell_window_open = true;
+                        ui.close_menu();
                     }
+                    ui.menu_button("Wrap atoms to unit cell", |ui| {
+                        if let Some(all_frames) = super::wrap_atoms::show_menu(ui, &mut state.wrap_keep_molecules) {
+                            let keep_whole = state.wrap_keep_molecules;
+                            state.message = super::wrap_atoms::wrap_trajectory(
+                                &mut traj,
+                                &current_frame,
+                                &mut history,
+                                all_frames,
+                                keep_whole,
+                            );
+                        }
+                    });
                     if ui.button("Build supercell…").clicked() {
-                        state.message = "no implemented yet".into();
+                        state.supercell_window_open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Build surface slab…").clicked() {
+                        state.slab_window_open = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Symmetry…").clicked() {
+                        state.symmetry_window_open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("Lattice planes…").clicked() {
+                        state.lattice_plane_window_open = true;
+                        ui.close_menu();
                     }
                 });
 
@@ -519,6 +733,14 @@ mod panel {
                         state.periodic_table_window_open = true;
                         ui.close_menu();
                     }
+                    if ui
+                        .button("Monitors…")
+                        .on_hover_text("Plot distances or angles over trajectory frames")
+                        .clicked()
+                    {
+                        state.monitor_panel_open = true;
+                        ui.close_menu();
+                    }
                     if ui.button("Input files generator…").clicked() {
                         ui.close_menu();
                         // Spawn a second window
@@ -549,8 +771,33 @@ mod panel {
             if ui.checkbox(&mut state.label_atoms_checked, "Label atoms").clicked() {
                 action = Action::LabelAtoms;
             }
+            ui.checkbox(&mut state.move_atoms_enabled, "Move atoms")
+                .on_hover_text("Drag an atom, or a selected atom to move all selected ones, in the screen plane");
             // atom selection
             state.atom_selection.show(ui, &mut selection_query);
+            ui.separator();
+            // avoid marking measurements changed every frame
+            let mut new_measurements = measurements.clone();
+            let to_set = new_measurements.show(ui, &selected_atoms.0, traj.get_current_molecule(&current_frame));
+            if new_measurements != *measurements {
+                *measurements = new_measurements;
+            }
+            if let Some((m, target)) = to_set {
+                // edit a copy, so that nothing is recorded for undo on failure
+                if let Some(mut mol) = traj.get_current_molecule(&current_frame).cloned() {
+                    match m.set_value(&mut mol, target) {
+                        Ok(_) => {
+                            state.message = m.format(&mol);
+                            history.record_current_frame(format!("Set {}", m.name()), &traj, &current_frame);
+                            if let Some(current) = traj.get_current_molecule_mut(&current_frame) {
+                                *current = mol;
+                            }
+                        }
+                        Err(err) => state.message = err,
+                    }
+                }
+            }
+            ui.separator();
             // show animation control button
             if let Some(iframe) = traj.get_current_frame_index(&current_frame) {
                 ui.horizontal(|ui| {
@@ -577,24 +824,22 @@ mod panel {
             ui.label(&state.message);
         });
 
-        // ui for periodic table
-        egui::Window::new("Periodic Table")
-            .id(egui::Id::new("periodic_table"))
-            // will be activated by menu item: tools/periodic table
-            .open(&mut state.periodic_table_window_open)
-            .anchor(egui::Align2::CENTER_TOP, [0.0, 0.0])
-            .collapsible(false)
-            .default_width(500.0)
-            .show(ctx, super::periodic_table::show);
-
         match action {
             Action::None => {}
             Action::Load => app.load_trajectory(state, writer),
-            Action::Save => app.save_trajectory(traj, state),
-            Action::Clear => app.clear_molecules(commands, state, label_events, molecule_query),
+            Action::Save => app.save_trajectory(traj, &current_frame, state),
+            Action::Clear => app.clear_molecules(traj, state, label_events, &mut history),
             Action::LabelAtoms => app.label_atoms(state, label_events, selection_query, atoms_query),
-            _ => {
-                state.message = format!("handler for action {action:?} is not implemented yet");
+            Action::UnbuildCrystal => {
+                if traj.get_current_molecule(&current_frame).is_some_and(|mol| mol.get_lattice().is_some()) {
+                    history.record_current_frame("Unbuild crystal", &traj, &current_frame);
+                    if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+                        mol.unbuild_crystal();
+                    }
+                    state.message = "Lattice removed".into();
+                } else {
+                    state.message = "No lattice in current molecule".into();
+                }
             }
         }
     }
@@ -752,14 +997,17 @@ mod periodic_table {
         }
     }
 
-    fn new_element_button(ui: &mut Ui, symbol: &str, color: Color32) {
+    /// Show button for element `symbol`, which will be chosen as
+    /// `selected` on click.
+    fn new_element_button(ui: &mut Ui, symbol: &str, color: Color32, selected: &mut String) {
         let atom = Atom::new(symbol, [0.0; 3]);
         let name = atom.kind().name();
         let number = format!("{}", atom.number());
         let cov_radius = format!("{}", atom.get_cov_radius().map(|x| x.to_string()).unwrap_or("N/A".into()));
         let vdw_radius = format!("{}", atom.get_vdw_radius().map(|x| x.to_string()).unwrap_or("N/A".into()));
         let symbol_txt = format!("{symbol:2}");
-        ui.add(Button::new(symbol_txt).fill(color)).on_hover_ui(|ui| {
+        let color = if selected == symbol { Color32::LIGHT_BLUE } else { color };
+        let response = ui.add(Button::new(symbol_txt).fill(color)).on_hover_ui(|ui| {
             ui.horizontal(|ui| {
                 ui.label("Name:");
                 ui.label(name);
@@ -781,105 +1029,109 @@ mod periodic_table {
                 ui.label(electronic_configuration(symbol));
             });
         });
+        if response.clicked() {
+            *selected = symbol.to_owned();
+        }
     }
 
-    pub fn show(ui: &mut Ui) {
+    /// Show periodic table for choosing an element as `selected`.
+    pub fn show(ui: &mut Ui, selected: &mut String) {
         egui::Grid::new("grid_periodic_table")
             .striped(true)
             .num_columns(18)
             .min_col_width(15.0)
             .show(ui, |ui| {
-                new_element_button(ui, "H", Color32::WHITE);
+                new_element_button(ui, "H", Color32::WHITE, selected);
                 // void space
                 for _ in 0..16 {
                     ui.label("");
                 }
-                new_element_button(ui, "He", Color32::WHITE);
+                new_element_button(ui, "He", Color32::WHITE, selected);
                 ui.end_row();
                 // 2
-                new_element_button(ui, "Li", Color32::WHITE);
-                new_element_button(ui, "Be", Color32::WHITE);
+                new_element_button(ui, "Li", Color32::WHITE, selected);
+                new_element_button(ui, "Be", Color32::WHITE, selected);
                 // void space
                 for _ in 0..10 {
                     ui.label("");
                 }
-                new_element_button(ui, "B", Color32::WHITE);
-                new_element_button(ui, "C", Color32::WHITE);
-                new_element_button(ui, "N", Color32::WHITE);
-                new_element_button(ui, "O", Color32::WHITE);
-                new_element_button(ui, "F", Color32::WHITE);
-                new_element_button(ui, "Ne", Color32::WHITE);
+                new_element_button(ui, "B", Color32::WHITE, selected);
+                new_element_button(ui, "C", Color32::WHITE, selected);
+                new_element_button(ui, "N", Color32::WHITE, selected);
+                new_element_button(ui, "O", Color32::WHITE, selected);
+                new_element_button(ui, "F", Color32::WHITE, selected);
+                new_element_button(ui, "Ne", Color32::WHITE, selected);
                 ui.end_row();
                 // 3
-                new_element_button(ui, "Na", Color32::WHITE);
-                new_element_button(ui, "Mg", Color32::WHITE);
+                new_element_button(ui, "Na", Color32::WHITE, selected);
+                new_element_button(ui, "Mg", Color32::WHITE, selected);
                 // void space
                 for _ in 0..10 {
                     ui.label("");
                 }
-                new_element_button(ui, "Al", Color32::WHITE);
-                new_element_button(ui, "Si", Color32::WHITE);
-                new_element_button(ui, "P", Color32::WHITE);
-                new_element_button(ui, "S", Color32::WHITE);
-                new_element_button(ui, "Cl", Color32::WHITE);
-                new_element_button(ui, "Ar", Color32::WHITE);
+                new_element_button(ui, "Al", Color32::WHITE, selected);
+                new_element_button(ui, "Si", Color32::WHITE, selected);
+                new_element_button(ui, "P", Color32::WHITE, selected);
+                new_element_button(ui, "S", Color32::WHITE, selected);
+                new_element_button(ui, "Cl", Color32::WHITE, selected);
+                new_element_button(ui, "Ar", Color32::WHITE, selected);
                 ui.end_row();
-                new_element_button(ui, "K", Color32::WHITE);
-                new_element_button(ui, "Ca", Color32::WHITE);
-                new_element_button(ui, "Sc", Color32::WHITE);
-                new_element_button(ui, "Ti", Color32::WHITE);
-                new_element_button(ui, "V", Color32::WHITE);
-                new_element_button(ui, "Cr", Color32::WHITE);
-                new_element_button(ui, "Mn", Color32::WHITE);
-                new_element_button(ui, "Fe", Color32::WHITE);
-                new_element_button(ui, "Co", Color32::WHITE);
-                new_element_button(ui, "Ni", Color32::WHITE);
-                new_element_button(ui, "Cu", Color32::WHITE);
-                new_element_button(ui, "Zn", Color32::WHITE);
-                new_element_button(ui, "Ga", Color32::WHITE);
-                new_element_button(ui, "Ge", Color32::WHITE);
-                new_element_button(ui, "As", Color32::WHITE);
-                new_element_button(ui, "Se", Color32::WHITE);
-                new_element_button(ui, "Br", Color32::WHITE);
-                new_element_button(ui, "Kr", Color32::WHITE);
+                new_element_button(ui, "K", Color32::WHITE, selected);
+                new_element_button(ui, "Ca", Color32::WHITE, selected);
+                new_element_button(ui, "Sc", Color32::WHITE, selected);
+                new_element_button(ui, "Ti", Color32::WHITE, selected);
+                new_element_button(ui, "V", Color32::WHITE, selected);
+                new_element_button(ui, "Cr", Color32::WHITE, selected);
+                new_element_button(ui, "Mn", Color32::WHITE, selected);
+                new_element_button(ui, "Fe", Color32::WHITE, selected);
+                new_element_button(ui, "Co", Color32::WHITE, selected);
+                new_element_button(ui, "Ni", Color32::WHITE, selected);
+                new_element_button(ui, "Cu", Color32::WHITE, selected);
+                new_element_button(ui, "Zn", Color32::WHITE, selected);
+                new_element_button(ui, "Ga", Color32::WHITE, selected);
+                new_element_button(ui, "Ge", Color32::WHITE, selected);
+                new_element_button(ui, "As", Color32::WHITE, selected);
+                new_element_button(ui, "Se", Color32::WHITE, selected);
+                new_element_button(ui, "Br", Color32::WHITE, selected);
+                new_element_button(ui, "Kr", Color32::WHITE, selected);
                 ui.end_row();
-                new_element_button(ui, "Rb", Color32::WHITE);
-                new_element_button(ui, "Sr", Color32::WHITE);
-                new_element_button(ui, "Y", Color32::WHITE);
-                new_element_button(ui, "Zr", Color32::WHITE);
-                new_element_button(ui, "Nb", Color32::WHITE);
-                new_element_button(ui, "Mo", Color32::WHITE);
-                new_element_button(ui, "Tc", Color32::WHITE);
-                new_element_button(ui, "Ru", Color32::WHITE);
-                new_element_button(ui, "Rh", Color32::WHITE);
-                new_element_button(ui, "Pd", Color32::WHITE);
-                new_element_button(ui, "Ag", Color32::WHITE);
-                new_element_button(ui, "Cd", Color32::WHITE);
-                new_element_button(ui, "In", Color32::WHITE);
-                new_element_button(ui, "Sn", Color32::WHITE);
-                new_element_button(ui, "Sb", Color32::WHITE);
-                new_element_button(ui, "Te", Color32::WHITE);
-                new_element_button(ui, "I", Color32::WHITE);
-                new_element_button(ui, "Xe", Color32::WHITE);
+                new_element_button(ui, "Rb", Color32::WHITE, selected);
+                new_element_button(ui, "Sr", Color32::WHITE, selected);
+                new_element_button(ui, "Y", Color32::WHITE, selected);
+                new_element_button(ui, "Zr", Color32::WHITE, selected);
+                new_element_button(ui, "Nb", Color32::WHITE, selected);
+                new_element_button(ui, "Mo", Color32::WHITE, selected);
+                new_element_button(ui, "Tc", Color32::WHITE, selected);
+                new_element_button(ui, "Ru", Color32::WHITE, selected);
+                new_element_button(ui, "Rh", Color32::WHITE, selected);
+                new_element_button(ui, "Pd", Color32::WHITE, selected);
+                new_element_button(ui, "Ag", Color32::WHITE, selected);
+                new_element_button(ui, "Cd", Color32::WHITE, selected);
+                new_element_button(ui, "In", Color32::WHITE, selected);
+                new_element_button(ui, "Sn", Color32::WHITE, selected);
+                new_element_button(ui, "Sb", Color32::WHITE, selected);
+                new_element_button(ui, "Te", Color32::WHITE, selected);
+                new_element_button(ui, "I", Color32::WHITE, selected);
+                new_element_button(ui, "Xe", Color32::WHITE, selected);
                 ui.end_row();
-                new_element_button(ui, "Cs", Color32::WHITE);
-                new_element_button(ui, "Ba", Color32::WHITE);
-                new_element_button(ui, "La", Color32::WHITE);
-                new_element_button(ui, "Hf", Color32::WHITE);
-                new_element_button(ui, "Ta", Color32::WHITE);
-                new_element_button(ui, "W", Color32::WHITE);
-                new_element_button(ui, "Re", Color32::WHITE);
-                new_element_button(ui, "Os", Color32::WHITE);
-                new_element_button(ui, "Ir", Color32::WHITE);
-                new_element_button(ui, "Pt", Color32::WHITE);
-                new_element_button(ui, "Au", Color32::WHITE);
-                new_element_button(ui, "Hg", Color32::WHITE);
-                new_element_button(ui, "Tl", Color32::WHITE);
-                new_element_button(ui, "Pb", Color32::WHITE);
-                new_element_button(ui, "Bi", Color32::WHITE);
-                new_element_button(ui, "Po", Color32::WHITE);
-                new_element_button(ui, "At", Color32::WHITE);
-                new_element_button(ui, "Rn", Color32::WHITE);
+                new_element_button(ui, "Cs", Color32::WHITE, selected);
+                new_element_button(ui, "Ba", Color32::WHITE, selected);
+                new_element_button(ui, "La", Color32::WHITE, selected);
+                new_element_button(ui, "Hf", Color32::WHITE, selected);
+                new_element_button(ui, "Ta", Color32::WHITE, selected);
+                new_element_button(ui, "W", Color32::WHITE, selected);
+                new_element_button(ui, "Re", Color32::WHITE, selected);
+                new_element_button(ui, "Os", Color32::WHITE, selected);
+                new_element_button(ui, "Ir", Color32::WHITE, selected);
+                new_element_button(ui, "Pt", Color32::WHITE, selected);
+                new_element_button(ui, "Au", Color32::WHITE, selected);
+                new_element_button(ui, "Hg", Color32::WHITE, selected);
+                new_element_button(ui, "Tl", Color32::WHITE, selected);
+                new_element_button(ui, "Pb", Color32::WHITE, selected);
+                new_element_button(ui, "Bi", Color32::WHITE, selected);
+                new_element_button(ui, "Po", Color32::WHITE, selected);
+                new_element_button(ui, "At", Color32::WHITE, selected);
+                new_element_button(ui, "Rn", Color32::WHITE, selected);
             });
     }
 }
@@ -919,7 +1171,45 @@ impl Plugin for LabelPlugin {
         app.add_event::<AtomLabelEvent>()
             .init_resource::<UiState>()
             .init_resource::<compute::State>()
+            .init_resource::<element_styles::State>()
+            .init_resource::<lattice_plane::State>()
+            .init_resource::<saved_views::State>()
+            .init_resource::<measurement::State>()
+            .init_resource::<monitor::State>()
+            .init_resource::<move_atoms::State>()
+            .init_resource::<edit_atoms::State>()
+            .init_resource::<constraints::State>()
+            .init_resource::<unit_cell::State>()
+            .init_resource::<supercell::State>()
+            .init_resource::<slab::State>()
+            .init_resource::<symmetry::State>()
+            .init_resource::<history::History>()
+            .add_event::<history::HistoryEvent>()
+            .insert_resource(saved_views::SavedViews::load())
             .add_system(panel::side_panels)
+            .add_system(element_styles::element_styles_window_system.after(panel::side_panels))
+            .add_system(edit_atoms::periodic_table_window_system.after(panel::side_panels))
+            .add_system(constraints::constraints_window_system.after(panel::side_panels))
+            .add_system(unit_cell::unit_cell_window_system.after(panel::side_panels))
+            .add_system(supercell::supercell_window_system.after(panel::side_panels))
+            .add_system(slab::slab_window_system.after(panel::side_panels))
+            .add_system(symmetry::symmetry_window_system.after(panel::side_panels))
+            .add_system(history::undo_redo_shortcuts)
+            .add_system(history::handle_history_events.after(panel::side_panels))
+            .add_system(history::clear_history_on_load)
+            .add_system(lattice_plane::lattice_plane_window_system.after(panel::side_panels))
+            .add_system(lattice_plane::update_lattice_planes.after(lattice_plane::lattice_plane_window_system))
+            .add_system(monitor::monitor_panel_system.after(panel::side_panels))
+            .add_system(move_atoms::keep_selection_in_move_mode)
+            .add_system(move_atoms::drag_atoms.run_if(|state: Res<UiState>| state.move_atoms_enabled))
+            .add_system(orientation::orientation_overlay_system.after(monitor::monitor_panel_system))
+            .add_event::<camera_view::CameraViewEvent>()
+            .add_system(camera_view::handle_camera_view_events.after(panel::side_panels))
+            .add_system(camera_view::animate_camera_transition.after(camera_view::handle_camera_view_events))
+            .add_system(camera_view::request_redraw_on_transition)
+            .add_system(saved_views::saved_views_window_system.after(panel::side_panels))
+            .add_system(measurement::update_measurement_marks.after(panel::side_panels))
+            .add_system(measurement::draw_measurement_labels.after(panel::side_panels))
             .add_system(input::input_generator_window_system)
             .add_system(handle_atom_label_events)
             .add_system(update_atom_labels_with_camera);
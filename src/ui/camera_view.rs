@@ -0,0 +1,226 @@
+// [[file:../../bevy.note::a7c2e94d][a7c2e94d]]
+use bevy::prelude::*;
+use bevy_egui::egui;
+use egui::Ui;
+
+use crate::arcball::PanOrbitCamera;
+use crate::base::CurrentFrame;
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// a7c2e94d ends here
+
+// [[file:../../bevy.note::5d0b3f86][5d0b3f86]]
+/// Request to rotate the camera for viewing along a direction. The
+/// viewing direction points from the camera to its focus.
+#[derive(Debug, Clone, Copy)]
+pub enum CameraViewEvent {
+    /// View along a direction in Cartesian coordinates
+    Cartesian(Vec3),
+    /// View along lattice direction [uvw]
+    Lattice([i32; 3]),
+    /// View down the bond between two selected atoms, from the first
+    /// one to the second one
+    SelectedBond,
+    /// View normal to the plane of three selected atoms
+    SelectedPlane,
+}
+
+/// The duration of camera transition in seconds
+pub const TRANSITION_DURATION: f32 = 0.5;
+
+/// Camera transition on the orbit sphere. The orientation is
+/// interpolated with slerp, and the camera ends at the focus and radius
+/// of `PanOrbitCamera`.
+#[derive(Debug, Component)]
+pub struct CameraTransition {
+    timer: Timer,
+    /// The camera rotation, focus and radius at start
+    start: (Quat, Vec3, f32),
+    /// The camera rotation at end
+    rotation: Quat,
+}
+
+impl CameraTransition {
+    /// Start transition from camera `transform` orbiting `focus` at
+    /// `radius` to `rotation`.
+    pub fn new(transform: &Transform, focus: Vec3, radius: f32, rotation: Quat) -> Self {
+        Self {
+            timer: Timer::from_seconds(TRANSITION_DURATION, TimerMode::Once),
+            start: (transform.rotation, focus, radius),
+            rotation,
+        }
+    }
+}
+// 5d0b3f86 ends here
+
+// [[file:../../bevy.note::e4b18a62][e4b18a62]]
+/// Show menu items for camera view presets. `uvw` is the lattice
+/// direction being edited.
+pub fn show_menu(ui: &mut Ui, uvw: &mut [i32; 3], events: &mut EventWriter<CameraViewEvent>) {
+    let mut event = None;
+    ui.horizontal(|ui| {
+        for (label, v) in [("x", Vec3::X), ("y", Vec3::Y), ("z", Vec3::Z)] {
+            if ui.button(format!("+{label}")).clicked() {
+                event = Some(CameraViewEvent::Cartesian(v));
+            }
+            if ui.button(format!("-{label}")).clicked() {
+                event = Some(CameraViewEvent::Cartesian(-v));
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        for (label, v) in [("a", [1, 0, 0]), ("b", [0, 1, 0]), ("c", [0, 0, 1])] {
+            if ui.button(label).on_hover_text("View along lattice vector").clicked() {
+                event = Some(CameraViewEvent::Lattice(v));
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        for x in uvw.iter_mut() {
+            ui.add(egui::DragValue::new(x).speed(0.1).clamp_range(-20..=20));
+        }
+        if ui
+            .button("[uvw]")
+            .on_hover_text("View along lattice direction [uvw]")
+            .clicked()
+        {
+            event = Some(CameraViewEvent::Lattice(*uvw));
+        }
+    });
+    ui.separator();
+    if ui
+        .button("Along selected bond")
+        .on_hover_text("View down the bond of two selected atoms")
+        .clicked()
+    {
+        event = Some(CameraViewEvent::SelectedBond);
+    }
+    if ui
+        .button("Normal to selected plane")
+        .on_hover_text("View normal to the plane of three selected atoms")
+        .clicked()
+    {
+        event = Some(CameraViewEvent::SelectedPlane);
+    }
+    if let Some(event) = event {
+        events.send(event);
+        ui.close_menu();
+    }
+}
+// e4b18a62 ends here
+
+// [[file:../../bevy.note::93f6c0b7][93f6c0b7]]
+/// Return the viewing direction and the new focus for `event`.
+fn resolve_view(
+    event: CameraViewEvent,
+    mol: &gchemol::Molecule,
+    selected_atoms: &[usize],
+) -> Result<(Vec3, Option<Vec3>), &'static str> {
+    let position = |i: usize| mol.get_atom(i).map(|a| Vec3::from(a.position().map(|x| x as f32)));
+    let (direction, focus) = match event {
+        CameraViewEvent::Cartesian(v) => (v, None),
+        CameraViewEvent::Lattice(uvw) => {
+            let lat = mol.get_lattice().ok_or("No lattice in current molecule")?;
+            let v = crate::base::as_vec3(lat.to_cart(uvw.map(|x| x as f64)));
+            let o = crate::base::as_vec3(lat.to_cart([0.0; 3]));
+            (v - o, None)
+        }
+        CameraViewEvent::SelectedBond => {
+            let &[i, j] = selected_atoms else {
+                return Err("Please select two atoms");
+            };
+            let (pi, pj) = position(i).zip(position(j)).ok_or("Invalid atom selection")?;
+            (pj - pi, Some((pi + pj) * 0.5))
+        }
+        CameraViewEvent::SelectedPlane => {
+            let &[i, j, k] = selected_atoms else {
+                return Err("Please select three atoms");
+            };
+            let (pi, pj) = position(i).zip(position(j)).ok_or("Invalid atom selection")?;
+            let pk = position(k).ok_or("Invalid atom selection")?;
+            ((pj - pi).cross(pk - pi), Some((pi + pj + pk) / 3.0))
+        }
+    };
+    let direction = direction.try_normalize().ok_or("Invalid viewing direction")?;
+    Ok((direction, focus))
+}
+
+/// Return the camera rotation viewing along `direction`.
+fn view_rotation(direction: Vec3) -> Quat {
+    // keep y axis up unless viewing along it
+    let up = if direction.dot(Vec3::Y).abs() < 0.99 {
+        Vec3::Y
+    } else {
+        Vec3::Z
+    };
+    Transform::IDENTITY.looking_at(direction, up).rotation
+}
+
+/// Rotate the camera smoothly on `CameraViewEvent`.
+pub fn handle_camera_view_events(
+    mut commands: Commands,
+    mut events: EventReader<CameraViewEvent>,
+    mut ui_state: ResMut<super::UiState>,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    selected_atoms: Res<SelectedAtoms>,
+    mut camera_query: Query<(Entity, &Transform, &mut PanOrbitCamera)>,
+) {
+    for &event in events.iter() {
+        let Some(mol) = traj.get_current_molecule(&current_frame) else {
+            continue;
+        };
+        match resolve_view(event, mol, &selected_atoms.0) {
+            Ok((direction, focus)) => {
+                for (entity, transform, mut pan_orbit) in camera_query.iter_mut() {
+                    let transition =
+                        CameraTransition::new(transform, pan_orbit.focus, pan_orbit.radius, view_rotation(direction));
+                    commands.entity(entity).insert(transition);
+                    if let Some(focus) = focus {
+                        pan_orbit.focus = focus;
+                    }
+                }
+            }
+            Err(msg) => ui_state.message = msg.into(),
+        }
+    }
+}
+
+/// Move cameras in transition along the orbit sphere, so that they
+/// never pass through the focus between opposite views.
+pub fn animate_camera_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut camera_query: Query<(Entity, &mut CameraTransition, &mut Transform, &PanOrbitCamera)>,
+) {
+    for (entity, mut transition, mut transform, pan_orbit) in camera_query.iter_mut() {
+        transition.timer.tick(time.delta());
+        // quadratic easing in and out
+        let t = transition.timer.percent();
+        let s = if t < 0.5 {
+            2.0 * t * t
+        } else {
+            1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+        };
+        let (rotation, focus, radius) = transition.start;
+        let rotation = rotation.slerp(transition.rotation, s);
+        let focus = focus.lerp(pan_orbit.focus, s);
+        let radius = radius + (pan_orbit.radius - radius) * s;
+        transform.rotation = rotation;
+        transform.translation = focus - rotation * Vec3::NEG_Z * radius;
+        if transition.timer.finished() {
+            commands.entity(entity).remove::<CameraTransition>();
+        }
+    }
+}
+
+/// Keep redrawing during camera transition, which is required for
+/// `WinitSettings::desktop_app`.
+pub fn request_redraw_on_transition(
+    transition_query: Query<(), With<CameraTransition>>,
+    mut redraw: EventWriter<bevy::window::RequestRedraw>,
+) {
+    if !transition_query.is_empty() {
+        redraw.send(bevy::window::RequestRedraw);
+    }
+}
+// 93f6c0b7 ends here
@@ -0,0 +1,125 @@
+// [[file:../../bevy.note::6d2f8b13][6d2f8b13]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+use gchemol::Molecule;
+
+use crate::base::CurrentFrame;
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// 6d2f8b13 ends here
+
+// [[file:../../bevy.note::b83e4a90][b83e4a90]]
+/// Set freezing flags of `atoms` in `mol`.
+pub fn set_freezing(mol: &mut Molecule, atoms: &[usize], freezing: [bool; 3]) {
+    for &i in atoms {
+        if let Some(a) = mol.get_atom_mut(i) {
+            a.set_freezing(freezing);
+        }
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct State {
+    /// Freezing flags in x, y, z to be applied
+    freezing: [bool; 3],
+    /// The selection which `freezing` was taken from
+    selection: Vec<usize>,
+}
+
+/// Edits of freezing constraints
+enum Action {
+    /// Apply freezing flags to selected atoms
+    Apply,
+    /// Unfreeze selected atoms
+    Unfreeze,
+    /// Unfreeze all atoms in current molecule
+    UnfreezeAll,
+}
+
+impl State {
+    fn show(&mut self, ui: &mut Ui, mol: &Molecule, selected_atoms: &[usize]) -> Option<Action> {
+        // take flags from the first selected atom on selection changes
+        if self.selection != selected_atoms {
+            self.selection = selected_atoms.to_vec();
+            if let Some(a) = selected_atoms.first().and_then(|&i| mol.get_atom(i)) {
+                self.freezing = a.freezing();
+            }
+        }
+
+        let nfrozen = mol.atoms().filter(|(_, a)| a.freezing().contains(&true)).count();
+        ui.label(format!("{} atoms selected, {nfrozen} atoms frozen", selected_atoms.len()));
+        ui.horizontal(|ui| {
+            ui.label("Freeze");
+            for (flag, axis) in self.freezing.iter_mut().zip(["x", "y", "z"]) {
+                ui.checkbox(flag, axis);
+            }
+        });
+
+        let mut action = None;
+        let has_selection = !selected_atoms.is_empty();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Apply"))
+                .on_hover_text("Apply freezing flags to selected atoms")
+                .clicked()
+            {
+                action = Some(Action::Apply);
+            }
+            if ui.add_enabled(has_selection, egui::Button::new("Unfreeze")).clicked() {
+                action = Some(Action::Unfreeze);
+            }
+            if ui
+                .add_enabled(nfrozen > 0, egui::Button::new("Unfreeze all"))
+                .on_hover_text("Unfreeze all atoms in current molecule")
+                .clicked()
+            {
+                action = Some(Action::UnfreezeAll);
+            }
+        });
+        action
+    }
+}
+// b83e4a90 ends here
+
+// [[file:../../bevy.note::f4a6c2d8][f4a6c2d8]]
+/// Edit freezing constraints of atoms in current frame.
+pub fn constraints_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    mut traj: ResMut<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    selected_atoms: Res<SelectedAtoms>,
+    mut history: ResMut<super::history::History>,
+) {
+    if !ui_state.constraints_window_open {
+        return;
+    }
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+
+    let ctx = contexts.ctx_mut();
+    let mut action = None;
+    egui::Window::new("Constraints")
+        .open(&mut ui_state.constraints_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            action = state.show(ui, mol, &selected_atoms.0);
+        });
+
+    let Some(action) = action else {
+        return;
+    };
+    let (description, atoms, freezing) = match action {
+        Action::Apply => ("Freeze atoms", selected_atoms.0.clone(), state.freezing),
+        Action::Unfreeze => ("Unfreeze atoms", selected_atoms.0.clone(), [false; 3]),
+        Action::UnfreezeAll => ("Unfreeze all atoms", mol.numbers().collect(), [false; 3]),
+    };
+    history.record_current_frame(description, &traj, &current_frame);
+    if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+        set_freezing(mol, &atoms, freezing);
+        ui_state.message = format!("{description}: {} atoms", atoms.len());
+    }
+}
+// f4a6c2d8 ends here
@@ -0,0 +1,205 @@
+// [[file:../../bevy.note::7b1d9e54][7b1d9e54]]
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+use gchemol::{Atom, Bond, Molecule};
+
+use crate::base::CurrentFrame;
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// 7b1d9e54 ends here
+
+// [[file:../../bevy.note::c52a8f07][c52a8f07]]
+/// The bond length in Å used when covalent radius is not available
+const DEFAULT_BOND_LENGTH: f64 = 1.5;
+
+/// Change element of `atoms` in `mol` to `symbol`.
+pub fn mutate_atoms(mol: &mut Molecule, atoms: &[usize], symbol: &str) {
+    for &i in atoms {
+        if mol.get_atom(i).is_some() {
+            mol.set_symbol(i, symbol);
+        }
+    }
+}
+
+/// Remove `atoms` from `mol` together with their bonds.
+pub fn remove_atoms(mol: &mut Molecule, atoms: &[usize]) {
+    for &i in atoms {
+        mol.remove_atom(i);
+    }
+}
+
+/// Return the direction for a new bond on atom `sn`, pointing away
+/// from its bonded neighbors.
+fn new_bond_direction(mol: &Molecule, sn: usize) -> DVec3 {
+    let p0 = DVec3::from(mol.get_atom_unchecked(sn).position());
+    let bonds: Vec<_> = mol
+        .connected(sn)
+        .filter_map(|j| {
+            let d = DVec3::from(mol.get_atom(j)?.position()) - p0;
+            let d = match mol.get_lattice() {
+                Some(lat) => DVec3::from(<[f64; 3]>::from(lat.apply_mic(d.to_array()))),
+                None => d,
+            };
+            d.try_normalize()
+        })
+        .collect();
+
+    let sum: DVec3 = bonds.iter().sum();
+    match bonds.as_slice() {
+        [] => DVec3::X,
+        _ if sum.length() > 0.1 => -sum.normalize(),
+        // neighbors cancel out, e.g. in linear or planar coordination
+        [u] => u.any_orthonormal_vector(),
+        [u, v, ..] => u.cross(*v).try_normalize().unwrap_or_else(|| u.any_orthonormal_vector()),
+    }
+}
+
+/// Add a new atom of `symbol` bonded to atom `sn` in `mol`. The bond
+/// length is the sum of covalent radii. Return serial number of the new
+/// atom.
+pub fn add_bonded_atom(mol: &mut Molecule, sn: usize, symbol: &str) -> Result<usize, String> {
+    let atom = mol.get_atom(sn).ok_or(format!("Atom {sn} not found"))?;
+    let mut new_atom = Atom::new(symbol, [0.0; 3]);
+    let length = atom
+        .get_cov_radius()
+        .zip(new_atom.get_cov_radius())
+        .map(|(r1, r2)| r1 + r2)
+        .unwrap_or(DEFAULT_BOND_LENGTH);
+    let p = DVec3::from(atom.position()) + new_bond_direction(mol, sn) * length;
+    new_atom.set_position(p.to_array());
+
+    let new_sn = mol.atoms().map(|(i, _)| i).max().unwrap_or(0) + 1;
+    mol.add_atom(new_sn, new_atom);
+    mol.add_bond(sn, new_sn, Bond::single());
+    Ok(new_sn)
+}
+// c52a8f07 ends here
+
+// [[file:../../bevy.note::0e8c4b7d][0e8c4b7d]]
+#[derive(Debug, Resource)]
+pub struct State {
+    /// The element chosen in periodic table
+    element: String,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { element: "C".to_owned() }
+    }
+}
+
+/// Edits of atoms in current molecule
+enum Action {
+    Mutate,
+    Add,
+    Delete,
+}
+
+impl State {
+    fn show(&mut self, ui: &mut Ui, selected_atoms: &[usize]) -> Option<Action> {
+        super::periodic_table::show(ui, &mut self.element);
+        ui.separator();
+
+        let mut action = None;
+        let atom = Atom::new(self.element.as_str(), [0.0; 3]);
+        let name = atom.kind().name();
+        ui.label(format!("Chosen element: {} ({name})", self.element));
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!selected_atoms.is_empty(), egui::Button::new("Change selected atoms"))
+                .on_hover_text("Change selected atoms to the chosen element")
+                .clicked()
+            {
+                action = Some(Action::Mutate);
+            }
+            if ui
+                .add_enabled(selected_atoms.len() == 1, egui::Button::new("Add bonded atom"))
+                .on_hover_text("Add an atom of the chosen element bonded to the selected atom")
+                .clicked()
+            {
+                action = Some(Action::Add);
+            }
+            if ui
+                .add_enabled(!selected_atoms.is_empty(), egui::Button::new("Delete selected atoms"))
+                .clicked()
+            {
+                action = Some(Action::Delete);
+            }
+        });
+        action
+    }
+}
+// 0e8c4b7d ends here
+
+// [[file:../../bevy.note::5f9a3c21][5f9a3c21]]
+/// Show periodic table for editing atoms in current frame. The
+/// displayed entities will be updated in `traj_animation_player`.
+pub fn periodic_table_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    mut traj: ResMut<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut selected_atoms: ResMut<SelectedAtoms>,
+    mut history: ResMut<super::history::History>,
+) {
+    if !ui_state.periodic_table_window_open {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+    let mut action = None;
+    egui::Window::new("Periodic Table")
+        .id(egui::Id::new("periodic_table"))
+        // will be activated by menu item: tools/periodic table
+        .open(&mut ui_state.periodic_table_window_open)
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 0.0])
+        .collapsible(false)
+        .default_width(500.0)
+        .show(ctx, |ui| {
+            action = state.show(ui, &selected_atoms.0);
+        });
+
+    let Some(action) = action else {
+        return;
+    };
+    let description = match action {
+        Action::Mutate => format!("Change atoms to {}", state.element),
+        Action::Add => format!("Add {} atom", state.element),
+        Action::Delete => "Delete atoms".to_owned(),
+    };
+    // edit a copy, so that nothing is recorded for undo on failure
+    let Some(mut mol) = traj.get_current_molecule(&current_frame).cloned() else {
+        ui_state.message = "No molecule present".into();
+        return;
+    };
+    let atoms = &selected_atoms.0;
+    let symbol = state.element.as_str();
+    let result = match action {
+        Action::Mutate => {
+            mutate_atoms(&mut mol, atoms, symbol);
+            Ok(format!("{} atoms changed to {symbol}", atoms.len()))
+        }
+        Action::Add => add_bonded_atom(&mut mol, atoms[0], symbol).map(|sn| format!("{symbol} atom {sn} added")),
+        Action::Delete => {
+            remove_atoms(&mut mol, atoms);
+            Ok(format!("{} atoms deleted", atoms.len()))
+        }
+    };
+    let message = match result {
+        Ok(message) => message,
+        Err(err) => {
+            ui_state.message = err;
+            return;
+        }
+    };
+    history.record_current_frame(description, &traj, &current_frame);
+    if let Some(current) = traj.get_current_molecule_mut(&current_frame) {
+        *current = mol;
+    }
+    ui_state.message = message;
+    // atom entities will be respawned without selection
+    selected_atoms.0.clear();
+}
+// 5f9a3c21 ends here
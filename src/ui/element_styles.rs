@@ -0,0 +1,169 @@
+// [[file:../../bevy.note::5b0f7d2e][5b0f7d2e]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+
+use crate::base::{ElementStyle, ElementStyles, RepresentationSettings};
+// 5b0f7d2e ends here
+
+// [[file:../../bevy.note::a41c96d3][a41c96d3]]
+#[derive(Debug, Default, Resource)]
+pub struct State {
+    /// The element styles being edited. Will be read from current
+    /// settings on window opening.
+    styles: Option<ElementStyles>,
+    /// The element symbol for adding a new style
+    new_symbol: String,
+    message: String,
+}
+// a41c96d3 ends here
+
+// [[file:../../bevy.note::e7d20b58][e7d20b58]]
+/// Show a checkbox for overriding the default `value`, and edit it using `add_contents`.
+fn edit_optional<T: Copy>(ui: &mut Ui, value: &mut Option<T>, default: T, add_contents: impl FnOnce(&mut Ui, &mut T)) {
+    let mut enabled = value.is_some();
+    if ui.checkbox(&mut enabled, "").on_hover_text("Override the default").changed() {
+        *value = enabled.then_some(default);
+    }
+    if let Some(v) = value {
+        add_contents(ui, v);
+    } else {
+        ui.label("default");
+    }
+}
+
+impl State {
+    /// Show the editor for element styles. Return the styles to be
+    /// applied if requested.
+    fn show(&mut self, ui: &mut Ui, current: &ElementStyles) -> Option<ElementStyles> {
+        let styles = self.styles.get_or_insert_with(|| current.clone());
+
+        let mut to_remove = None;
+        egui::Grid::new("element_styles_grid").striped(true).show(ui, |ui| {
+            ui.label("Element");
+            ui.label("Color");
+            ui.label("Radius (Å)");
+            ui.label("Bond radius (Å)");
+            ui.end_row();
+            for (symbol, style) in styles.elements.iter_mut() {
+                let builtin = ElementStyle::builtin(symbol);
+                ui.label(symbol);
+                ui.horizontal(|ui| {
+                    edit_optional(ui, &mut style.color, builtin.color.unwrap_or_default(), |ui, color| {
+                        ui.color_edit_button_srgb(color);
+                    });
+                });
+                ui.horizontal(|ui| {
+                    edit_optional(ui, &mut style.radius, builtin.radius.unwrap_or(0.5), |ui, r| {
+                        ui.add(egui::DragValue::new(r).speed(0.01).clamp_range(0.01..=5.0));
+                    });
+                });
+                ui.horizontal(|ui| {
+                    edit_optional(ui, &mut style.bond_radius, builtin.bond_radius.unwrap_or(0.07), |ui, r| {
+                        ui.add(egui::DragValue::new(r).speed(0.01).clamp_range(0.01..=1.0));
+                    });
+                });
+                if ui.button("❌").on_hover_text("Remove style of this element").clicked() {
+                    to_remove = Some(symbol.to_owned());
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(symbol) = to_remove {
+            styles.elements.remove(&symbol);
+        }
+
+        ui.horizontal(|ui| {
+            let button = ui.button("Add").on_hover_text("Add style for element");
+            if ui
+                .add(egui::TextEdit::singleline(&mut self.new_symbol).desired_width(40.0))
+                .on_hover_text("Element symbol, e.g. H")
+                .lost_focus()
+                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                || button.clicked()
+            {
+                let symbol = self.new_symbol.trim();
+                if gchemol::Atom::new(symbol, [0.0; 3]).number() == 0 {
+                    self.message = format!("Invalid element symbol: {symbol:?}");
+                } else {
+                    styles.elements.entry(symbol.to_owned()).or_default();
+                    self.new_symbol.clear();
+                }
+            }
+        });
+        ui.separator();
+
+        let path = ElementStyles::default_path();
+        let mut apply = None;
+        ui.horizontal(|ui| {
+            if ui.button("Apply").clicked() {
+                apply = Some(styles.clone());
+            }
+            if ui
+                .button("💾 Save")
+                .on_hover_text("Apply and save to user config file")
+                .clicked()
+            {
+                apply = Some(styles.clone());
+                if let Some(path) = &path {
+                    match styles.to_file(path) {
+                        Ok(_) => self.message = format!("Saved to {path:?}"),
+                        Err(err) => self.message = format!("{err:?}"),
+                    }
+                } else {
+                    self.message = "User config directory not found".into();
+                }
+            }
+            if ui
+                .button("Reset to defaults")
+                .on_hover_text("Remove all user defined styles together with user config file")
+                .clicked()
+            {
+                *styles = ElementStyles::default();
+                apply = Some(styles.clone());
+                self.message = match &path {
+                    Some(path) if path.exists() => match std::fs::remove_file(path) {
+                        Ok(_) => format!("Reset to defaults, and removed {path:?}"),
+                        Err(err) => format!("Reset to defaults, but failed to remove {path:?}: {err}"),
+                    },
+                    _ => "Reset to defaults".into(),
+                };
+            }
+        });
+        if let Some(path) = path {
+            ui.label(format!("Config file: {}", path.display()));
+        }
+        if !self.message.is_empty() {
+            ui.label(&self.message);
+        }
+        apply
+    }
+}
+// e7d20b58 ends here
+
+// [[file:../../bevy.note::0d8e6a4c][0d8e6a4c]]
+pub fn element_styles_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    mut settings: ResMut<RepresentationSettings>,
+) {
+    if !ui_state.element_styles_window_open {
+        // read from current settings on next opening
+        state.styles = None;
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+    let mut apply = None;
+    egui::Window::new("Element styles")
+        .open(&mut ui_state.element_styles_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            apply = state.show(ui, settings.styles());
+        });
+    if let Some(styles) = apply {
+        settings.set_styles(styles);
+    }
+}
+// 0d8e6a4c ends here
@@ -0,0 +1,239 @@
+// [[file:../../bevy.note::9a4e17c6][9a4e17c6]]
+use bevy::prelude::*;
+use bevy_egui::EguiContexts;
+use bevy_mod_picking::prelude::PickSelection;
+use gchemol::Molecule;
+
+use crate::base::CurrentFrame;
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// 9a4e17c6 ends here
+
+// [[file:../../bevy.note::e2b85d0f][e2b85d0f]]
+/// The max number of edits kept for undo
+const MAX_HISTORY: usize = 100;
+
+/// A change to be applied on trajectory for reverting an edit
+#[derive(Debug, Clone)]
+enum Change {
+    /// Replace the molecule in a frame
+    Replace(usize, Molecule),
+    /// Insert a molecule as a frame
+    Insert(usize, Molecule),
+    /// Remove a frame
+    Remove(usize),
+    /// Replace all frames
+    ReplaceAll(Vec<Molecule>),
+}
+
+impl Change {
+    /// Apply this change on `traj`. Return the change reverting it.
+    fn apply(self, traj: &mut MoleculeTrajectory) -> Option<Self> {
+        let change = match self {
+            Self::Replace(i, mol) => Self::Replace(i, traj.replace_frame(i, mol)?),
+            Self::Insert(i, mol) => {
+                let i = i.min(traj.nframes());
+                traj.insert_frame(i, mol);
+                Self::Remove(i)
+            }
+            Self::Remove(i) => Self::Insert(i, traj.remove_frame(i)?),
+            Self::ReplaceAll(mols) => Self::ReplaceAll(traj.replace_frames(mols)),
+        };
+        Some(change)
+    }
+
+    /// The frame affected by this change.
+    fn frame(&self) -> Option<usize> {
+        match self {
+            Self::Replace(i, _) | Self::Insert(i, _) | Self::Remove(i) => Some(*i),
+            Self::ReplaceAll(_) => None,
+        }
+    }
+}
+
+/// A recorded edit with its description
+#[derive(Debug, Clone)]
+struct Edit {
+    description: String,
+    change: Change,
+}
+
+/// Undo/redo history of edits on `MoleculeTrajectory`. An edit is
+/// recorded as the snapshot of affected frames before editing.
+#[derive(Debug, Default, Resource)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Counter of recorded, reverted or reapplied edits
+    revision: usize,
+    /// The frame affected by the latest edit. None if all frames may be
+    /// affected.
+    last_frame: Option<usize>,
+}
+
+impl History {
+    /// Count an edit affecting `frame`, or all frames if None.
+    fn touch(&mut self, frame: Option<usize>) {
+        self.revision += 1;
+        self.last_frame = frame;
+    }
+
+    fn push(&mut self, description: impl Into<String>, change: Change) {
+        let description = description.into();
+        info!("record edit: {description}");
+        self.touch(change.frame());
+        self.undo.push(Edit { description, change });
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Record frame `index` of `traj` before editing it.
+    pub fn record_frame(&mut self, description: impl Into<String>, traj: &MoleculeTrajectory, index: usize) {
+        if let Some(mol) = traj.get_molecules().get(index) {
+            self.push(description, Change::Replace(index, mol.clone()));
+        }
+    }
+
+    /// Record current frame of `traj` before editing it.
+    pub fn record_current_frame(
+        &mut self,
+        description: impl Into<String>,
+        traj: &MoleculeTrajectory,
+        current_frame: &CurrentFrame,
+    ) {
+        if let Some(i) = traj.get_current_frame_index(current_frame) {
+            self.record_frame(description, traj, i);
+        }
+    }
+
+    /// Record all frames of `traj` before editing them.
+    pub fn record_trajectory(&mut self, description: impl Into<String>, traj: &MoleculeTrajectory) {
+        self.push(description, Change::ReplaceAll(traj.get_molecules().to_vec()));
+    }
+
+    /// Remove frame `index` from `traj` with recording.
+    pub fn remove_frame(&mut self, traj: &mut MoleculeTrajectory, index: usize) -> Option<()> {
+        let mol = traj.remove_frame(index)?;
+        self.push(format!("Delete frame {index}"), Change::Insert(index, mol));
+        Some(())
+    }
+
+    /// The description of next edit to undo.
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|e| e.description.as_str())
+    }
+
+    /// The description of next edit to redo.
+    pub fn next_redo(&self) -> Option<&str> {
+        self.redo.last().map(|e| e.description.as_str())
+    }
+
+    /// Forget all recorded edits, e.g. on loading new molecules.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.touch(None);
+    }
+
+    /// Return the revision counting edits, and the frame affected by
+    /// the latest edit. The frame may be edited again after recording,
+    /// e.g. on dragging atoms.
+    pub fn last_edit(&self) -> (usize, Option<usize>) {
+        (self.revision, self.last_frame)
+    }
+
+    /// Revert the last edit on `traj`. Return the reverted edit and
+    /// the affected frame.
+    fn undo(&mut self, traj: &mut MoleculeTrajectory) -> Option<(String, Option<usize>)> {
+        let edit = self.undo.pop()?;
+        let frame = edit.change.frame();
+        self.touch(frame);
+        let change = edit.change.apply(traj)?;
+        let description = edit.description.clone();
+        self.redo.push(Edit { change, ..edit });
+        Some((description, frame))
+    }
+
+    /// Reapply the last reverted edit on `traj`.
+    fn redo(&mut self, traj: &mut MoleculeTrajectory) -> Option<(String, Option<usize>)> {
+        let edit = self.redo.pop()?;
+        let frame = edit.change.frame();
+        self.touch(frame);
+        let change = edit.change.apply(traj)?;
+        let description = edit.description.clone();
+        self.undo.push(Edit { change, ..edit });
+        Some((description, frame))
+    }
+}
+// e2b85d0f ends here
+
+// [[file:../../bevy.note::41c7d3a8][41c7d3a8]]
+/// Request to undo or redo an edit
+#[derive(Debug, Clone, Copy)]
+pub enum HistoryEvent {
+    Undo,
+    Redo,
+}
+
+/// Undo with Ctrl+Z and redo with Ctrl+Shift+Z.
+pub fn undo_redo_shortcuts(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut contexts: EguiContexts,
+    mut events: EventWriter<HistoryEvent>,
+) {
+    // leave it for text editing in egui
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let ctrl = keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let shift = keyboard_input.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    if ctrl && keyboard_input.just_pressed(KeyCode::Z) {
+        events.send(if shift { HistoryEvent::Redo } else { HistoryEvent::Undo });
+    }
+}
+
+/// Apply undo or redo on trajectory and show the edited frame.
+pub fn handle_history_events(
+    mut events: EventReader<HistoryEvent>,
+    mut history: ResMut<History>,
+    mut traj: ResMut<MoleculeTrajectory>,
+    mut current_frame: ResMut<CurrentFrame>,
+    mut selected_atoms: ResMut<SelectedAtoms>,
+    mut selection_query: Query<&mut PickSelection>,
+    mut ui_state: ResMut<super::UiState>,
+) {
+    for event in events.iter() {
+        let (done, result) = match event {
+            HistoryEvent::Undo => ("Undo", history.undo(&mut traj)),
+            HistoryEvent::Redo => ("Redo", history.redo(&mut traj)),
+        };
+        let Some((description, frame)) = result else {
+            ui_state.message = format!("Nothing to {}", done.to_lowercase());
+            continue;
+        };
+        ui_state.message = format!("{done}: {description}");
+        if let Some(i) = frame.filter(|&i| i < traj.nframes()) {
+            current_frame.0 = i as isize;
+        }
+        // atoms may be changed, clear selection as on loading
+        selected_atoms.0.clear();
+        for mut selection in selection_query.iter_mut() {
+            if selection.is_selected {
+                selection.is_selected = false;
+            }
+        }
+    }
+}
+
+/// Forget edits of previous molecules on loading new ones.
+pub fn clear_history_on_load(mut reader: EventReader<crate::net::StreamEvent>, mut history: ResMut<History>) {
+    use crate::net::RemoteCommand;
+
+    for crate::net::StreamEvent(cmd) in reader.iter() {
+        if matches!(cmd, RemoteCommand::Load(_) | RemoteCommand::Delete) {
+            history.clear();
+        }
+    }
+}
+// 41c7d3a8 ends here
@@ -0,0 +1,140 @@
+// [[file:../../bevy.note::8c3e51a7][8c3e51a7]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+
+use crate::base::{CurrentFrame, LatticePlane, MoleculeAssets};
+use crate::molecule::MoleculeTrajectory;
+// 8c3e51a7 ends here
+
+// [[file:../../bevy.note::d27f0b94][d27f0b94]]
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub struct State {
+    /// Miller indices of lattice planes
+    hkl: [i32; 3],
+    /// The offset of planes along the normal, in units of interplanar
+    /// spacing
+    offset: f64,
+    /// The repetitions of the cell for which `hkl` are referenced to
+    supercell: [u32; 3],
+    /// Show lattice planes or not
+    visible: bool,
+    /// Lattice planes are only shown when the window is open
+    window_open: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            hkl: [1, 0, 0],
+            offset: 0.0,
+            supercell: [1, 1, 1],
+            visible: true,
+            window_open: false,
+        }
+    }
+}
+// d27f0b94 ends here
+
+// [[file:../../bevy.note::71f4a2c6][71f4a2c6]]
+impl State {
+    fn show(&mut self, ui: &mut Ui, lattice: Option<&gchemol::Lattice>) {
+        egui::Grid::new("lattice_plane_grid").show(ui, |ui| {
+            ui.label("Miller indices (h k l)");
+            ui.horizontal(|ui| {
+                for x in self.hkl.iter_mut() {
+                    ui.add(egui::DragValue::new(x).speed(0.1).clamp_range(-20..=20));
+                }
+            });
+            ui.end_row();
+            ui.label("Offset (d)");
+            ui.add(egui::Slider::new(&mut self.offset, 0.0..=1.0));
+            ui.end_row();
+            ui.label("Supercell")
+                .on_hover_text("Repetitions of the cell along a, b, c, for which (h k l) are referenced to");
+            ui.horizontal(|ui| {
+                for x in self.supercell.iter_mut() {
+                    ui.add(egui::DragValue::new(x).speed(0.1).clamp_range(1..=20));
+                }
+            });
+            ui.end_row();
+        });
+        ui.checkbox(&mut self.visible, "Show planes");
+        ui.separator();
+
+        match lattice {
+            Some(lat) => match crate::base::interplanar_spacing(lat, self.hkl, self.supercell) {
+                Some(d) => ui.label(format!("d(hkl) = {d:.4} Å")),
+                None => ui.label("Invalid Miller indices (0 0 0)"),
+            },
+            None => ui.label("No lattice in current molecule"),
+        };
+    }
+}
+// 71f4a2c6 ends here
+
+// [[file:../../bevy.note::3a9e6f08][3a9e6f08]]
+pub fn lattice_plane_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+) {
+    if !ui_state.lattice_plane_window_open {
+        if state.window_open {
+            state.window_open = false;
+        }
+        return;
+    }
+
+    let lattice = traj.get_current_molecule(&current_frame).and_then(|mol| mol.lattice.as_ref());
+    let ctx = contexts.ctx_mut();
+    // avoid marking state changed every frame
+    let mut new_state = state.clone();
+    new_state.window_open = true;
+    egui::Window::new("Lattice planes")
+        .open(&mut ui_state.lattice_plane_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            new_state.show(ui, lattice);
+        });
+    if new_state != *state {
+        *state = new_state;
+    }
+}
+
+/// Create or update lattice planes when planes setting, trajectory or
+/// current frame changed.
+pub fn update_lattice_planes(
+    mut commands: Commands,
+    state: Res<State>,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut assets: MoleculeAssets,
+    plane_query: Query<Entity, With<LatticePlane>>,
+) {
+    if !(state.is_changed() || traj.is_changed() || current_frame.is_changed()) {
+        return;
+    }
+
+    for entity in plane_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !state.window_open || !state.visible {
+        return;
+    }
+    let lattice = traj.get_current_molecule(&current_frame).and_then(|mol| mol.lattice.as_ref());
+    if let Some(lat) = lattice {
+        let MoleculeAssets {
+            meshes,
+            materials,
+            cache,
+        } = &mut assets;
+        let planes = crate::base::create_lattice_planes(lat, state.hkl, state.offset, state.supercell, meshes, materials, cache);
+        for plane in planes {
+            commands.spawn((plane, LatticePlane));
+        }
+    }
+}
+// 3a9e6f08 ends here
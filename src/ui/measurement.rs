@@ -0,0 +1,444 @@
+// [[file:../../bevy.note::d8a14f6e][d8a14f6e]]
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+
+use crate::base::{CurrentFrame, MoleculeAssets};
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// d8a14f6e ends here
+
+// [[file:../../bevy.note::47c0e2b5][47c0e2b5]]
+/// Geometric measurement of atoms in selection order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measurement {
+    Distance([usize; 2]),
+    Angle([usize; 3]),
+    Dihedral([usize; 4]),
+}
+
+impl Measurement {
+    /// Create measurement from 2, 3 or 4 atoms.
+    pub fn from_atoms(atoms: &[usize]) -> Option<Self> {
+        match *atoms {
+            [i, j] => Some(Self::Distance([i, j])),
+            [i, j, k] => Some(Self::Angle([i, j, k])),
+            [i, j, k, l] => Some(Self::Dihedral([i, j, k, l])),
+            _ => None,
+        }
+    }
+
+    /// Return serial numbers of atoms involved.
+    pub fn atoms(&self) -> &[usize] {
+        match self {
+            Self::Distance(x) => x,
+            Self::Angle(x) => x,
+            Self::Dihedral(x) => x,
+        }
+    }
+
+    /// Return the description, such as "distance(1-2)".
+    pub fn name(&self) -> String {
+        let kind = match self {
+            Self::Distance(_) => "distance",
+            Self::Angle(_) => "angle",
+            Self::Dihedral(_) => "dihedral",
+        };
+        let atoms: Vec<_> = self.atoms().iter().map(|x| x.to_string()).collect();
+        format!("{kind}({})", atoms.join("-"))
+    }
+
+    /// Return the unit of measured value.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Distance(_) => "Å",
+            _ => "°",
+        }
+    }
+
+    /// Return positions of involved atoms in `mol`. For periodic
+    /// structure, each atom is placed at the image nearest to its
+    /// predecessor, following the minimum image convention.
+    fn positions(&self, mol: &gchemol::Molecule) -> Option<Vec<DVec3>> {
+        let mut positions: Vec<DVec3> = vec![];
+        for &i in self.atoms() {
+            let p = DVec3::from(mol.get_atom(i)?.position());
+            let p = match (positions.last(), mol.get_lattice()) {
+                (Some(&prev), Some(lat)) => {
+                    let d: [f64; 3] = lat.apply_mic((p - prev).to_array()).into();
+                    prev + DVec3::from(d)
+                }
+                _ => p,
+            };
+            positions.push(p);
+        }
+        Some(positions)
+    }
+
+    /// Evaluate the distance in Å or angle in degrees in `mol`. Return
+    /// None if any atom does not exist.
+    pub fn evaluate(&self, mol: &gchemol::Molecule) -> Option<f64> {
+        let p = self.positions(mol)?;
+        let value = match self {
+            Self::Distance(_) => p[0].distance(p[1]),
+            Self::Angle(_) => (p[0] - p[1]).angle_between(p[2] - p[1]).to_degrees(),
+            Self::Dihedral(_) => {
+                let b1 = p[1] - p[0];
+                let b2 = p[2] - p[1];
+                let b3 = p[3] - p[2];
+                let y = b2.length() * b1.dot(b2.cross(b3));
+                let x = b1.cross(b2).dot(b2.cross(b3));
+                y.atan2(x).to_degrees()
+            }
+        };
+        Some(value)
+    }
+
+    /// Return formatted measurement in `mol`, such as "distance(1-2) = 1.0900 Å".
+    pub fn format(&self, mol: &gchemol::Molecule) -> String {
+        let name = self.name();
+        match (self, self.evaluate(mol)) {
+            (Self::Distance(_), Some(v)) => format!("{name} = {v:6.4} {}", self.unit()),
+            (_, Some(v)) => format!("{name} = {v:6.2} {}", self.unit()),
+            (_, None) => format!("{name} = N/A"),
+        }
+    }
+}
+// 47c0e2b5 ends here
+
+// [[file:../../bevy.note::c6f81d27][c6f81d27]]
+/// Return atoms with their positions in the fragment reached from
+/// `pivot` through bonds without crossing the bond between `pivot` and
+/// `fixed`. The fragment is unwrapped from `pivot` at position `p0`
+/// following the minimum image convention for periodic structure.
+/// Return None if the fragment contains any atom in `excluded`, e.g.
+/// in a ring.
+fn side_fragment(
+    mol: &gchemol::Molecule,
+    pivot: usize,
+    fixed: usize,
+    p0: DVec3,
+    excluded: &[usize],
+) -> Option<Vec<(usize, DVec3)>> {
+    use std::collections::HashSet;
+
+    let position = |i: usize| DVec3::from(mol.get_atom(i).expect("invalid atom").position());
+    let mut visited: HashSet<_> = [pivot].into();
+    let mut fragment = vec![(pivot, p0)];
+    let mut k = 0;
+    while let Some(&(i, pi)) = fragment.get(k) {
+        for j in mol.connected(i) {
+            if (i == pivot && j == fixed) || !visited.insert(j) {
+                continue;
+            }
+            if excluded.contains(&j) {
+                return None;
+            }
+            let d = position(j) - position(i);
+            let d = match mol.get_lattice() {
+                Some(lat) => DVec3::from(<[f64; 3]>::from(lat.apply_mic(d.to_array()))),
+                None => d,
+            };
+            fragment.push((j, pi + d));
+        }
+        k += 1;
+    }
+    Some(fragment)
+}
+
+impl Measurement {
+    /// Set the distance in Å or angle in degrees of this measurement in
+    /// `mol` to `target`, by moving the smaller fragment on either side
+    /// rigidly. Only the terminal atom will be moved if the fragments
+    /// cannot be separated, e.g. in a ring.
+    pub fn set_value(&self, mol: &mut gchemol::Molecule, target: f64) -> Result<(), String> {
+        use bevy::math::DQuat;
+
+        let p = self.positions(mol).ok_or("Invalid atoms")?;
+        let current = self.evaluate(mol).ok_or("Invalid atoms")?;
+        let atoms = self.atoms();
+        let n = atoms.len();
+        // the fragment reached from the pivot atom, which falls back to
+        // the terminal atom if inseparable
+        let fragment = |pivot: usize, fixed: usize, terminal: usize| {
+            let excluded: Vec<_> = (0..n).filter(|&k| k != pivot && k != terminal).map(|k| atoms[k]).collect();
+            side_fragment(mol, atoms[pivot], atoms[fixed], p[pivot], &excluded)
+                .unwrap_or_else(|| vec![(atoms[terminal], p[terminal])])
+        };
+        // the indices of pivot and fixed atoms for the end and start side
+        let ((end_pivot, end_fixed), (start_pivot, start_fixed)) = match self {
+            Self::Distance(_) => ((1, 0), (0, 1)),
+            Self::Angle(_) => ((2, 1), (0, 1)),
+            Self::Dihedral(_) => ((2, 1), (1, 2)),
+        };
+        let end_side = fragment(end_pivot, end_fixed, n - 1);
+        let start_side = fragment(start_pivot, start_fixed, 0);
+        // move the smaller fragment, in opposite direction for the start side
+        let (moving, sign) = if start_side.len() < end_side.len() {
+            (start_side, -1.0)
+        } else {
+            (end_side, 1.0)
+        };
+
+        let transform: Box<dyn Fn(DVec3) -> DVec3> = match self {
+            Self::Distance(_) => {
+                let d = (p[1] - p[0]).try_normalize().ok_or("Overlapping atoms")?;
+                let shift = d * (target - current) * sign;
+                Box::new(move |x| x + shift)
+            }
+            Self::Angle(_) => {
+                if !(0.0..=180.0).contains(&target) {
+                    return Err("Angle should be in range of 0 to 180 degrees".into());
+                }
+                let axis = (p[0] - p[1]).cross(p[2] - p[1]).try_normalize().ok_or("Linear angle")?;
+                let q = DQuat::from_axis_angle(axis, (target - current).to_radians() * sign);
+                let center = p[1];
+                Box::new(move |x| center + q * (x - center))
+            }
+            Self::Dihedral(_) => {
+                let axis = (p[2] - p[1]).try_normalize().ok_or("Overlapping atoms")?;
+                let q = DQuat::from_axis_angle(axis, (target - current).to_radians() * sign);
+                let center = p[1];
+                Box::new(move |x| center + q * (x - center))
+            }
+        };
+        for (i, pi) in moving {
+            mol.set_position(i, transform(pi).to_array());
+        }
+        Ok(())
+    }
+}
+// c6f81d27 ends here
+
+// [[file:../../bevy.note::0b9e3c71][0b9e3c71]]
+/// Dashed lines or arcs marking a measurement in 3D view
+#[derive(Clone, Copy, Debug, Component)]
+pub struct MeasurementMark;
+
+/// The number of segments approximating an arc
+const ARC_SEGMENTS: usize = 24;
+
+/// Return points on the arc centered at `center` with `radius`, from
+/// direction `u` to `w`.
+fn arc_points(center: DVec3, u: DVec3, w: DVec3, radius: f64) -> Option<Vec<DVec3>> {
+    let u = u.try_normalize()?;
+    let w = w.try_normalize()?;
+    let theta = u.angle_between(w);
+    // the arc direction is undefined for antiparallel vectors
+    if theta.sin().abs() < 1e-6 {
+        return None;
+    }
+    let points = (0..=ARC_SEGMENTS)
+        .map(|k| {
+            let t = k as f64 / ARC_SEGMENTS as f64;
+            let v = (u * ((1.0 - t) * theta).sin() + w * (t * theta).sin()) / theta.sin();
+            center + v * radius
+        })
+        .collect();
+    Some(points)
+}
+
+/// Return the polylines to draw and the position of text label for
+/// measurement `m` in `mol`.
+fn annotation(m: &Measurement, mol: &gchemol::Molecule) -> Option<(Vec<Vec<DVec3>>, DVec3)> {
+    let p = m.positions(mol)?;
+    match m {
+        Measurement::Distance(_) => Some((vec![p.clone()], (p[0] + p[1]) * 0.5)),
+        Measurement::Angle(_) => {
+            let (u, w) = (p[0] - p[1], p[2] - p[1]);
+            let radius = 0.4 * u.length().min(w.length()).min(1.5);
+            let arc = arc_points(p[1], u, w, radius)?;
+            let label = arc[ARC_SEGMENTS / 2];
+            Some((vec![vec![p[0], p[1], p[2]], arc], label))
+        }
+        Measurement::Dihedral(_) => {
+            // arc around the central bond
+            let axis = (p[2] - p[1]).try_normalize()?;
+            let u = (p[0] - p[1]).reject_from_normalized(axis);
+            let w = (p[3] - p[2]).reject_from_normalized(axis);
+            let center = (p[1] + p[2]) * 0.5;
+            let radius = 0.4 * p[1].distance(p[2]).min(1.5);
+            let arc = arc_points(center, u, w, radius)?;
+            let label = arc[ARC_SEGMENTS / 2];
+            Some((vec![arc], label))
+        }
+    }
+}
+
+/// Return measurements to be annotated: saved ones and the one from
+/// current selection.
+fn annotated<'a>(state: &'a State, selected_atoms: &[usize]) -> impl Iterator<Item = Measurement> + 'a {
+    let current = Measurement::from_atoms(selected_atoms).filter(|m| !state.items.contains(m));
+    state.items.iter().copied().chain(current)
+}
+// 0b9e3c71 ends here
+
+// [[file:../../bevy.note::e9d5a048][e9d5a048]]
+#[derive(Debug, Default, Clone, PartialEq, Resource)]
+pub struct State {
+    /// Saved measurements
+    pub items: Vec<Measurement>,
+    /// The target value for editing the measurement of selected atoms
+    target: Option<(Measurement, f64)>,
+}
+
+impl State {
+    /// Show measurement list for current molecule `mol`. The measurement
+    /// of selected atoms can be added to the list, or be set to a
+    /// target value. Return the measurement with target value to be
+    /// set if requested.
+    pub fn show(&mut self, ui: &mut Ui, selected_atoms: &[usize], mol: Option<&gchemol::Molecule>) -> Option<(Measurement, f64)> {
+        ui.label("Measurements");
+        let current = Measurement::from_atoms(selected_atoms);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(current.is_some(), egui::Button::new("Add"))
+                .on_hover_text("Measure distance, angle or dihedral of 2, 3 or 4 selected atoms in order")
+                .clicked()
+            {
+                if let Some(m) = current.filter(|m| !self.items.contains(m)) {
+                    self.items.push(m);
+                }
+            }
+            if ui.add_enabled(!self.items.is_empty(), egui::Button::new("Clear")).clicked() {
+                self.items.clear();
+            }
+        });
+
+        // edit geometry of selected atoms
+        let mut to_set = None;
+        let value = mol.zip(current).and_then(|(mol, m)| Some((m, m.evaluate(mol)?)));
+        if let Some((m, value)) = value {
+            // reset target on selection changes
+            if !self.target.is_some_and(|(x, _)| x == m) {
+                self.target = Some((m, value));
+            }
+            if let Some((_, target)) = self.target.as_mut() {
+                ui.horizontal(|ui| {
+                    let speed = if matches!(m, Measurement::Distance(_)) { 0.01 } else { 0.5 };
+                    ui.add(egui::DragValue::new(target).speed(speed).suffix(format!(" {}", m.unit())));
+                    if ui
+                        .button("Set")
+                        .on_hover_text("Move the smaller fragment rigidly to reach the target value")
+                        .clicked()
+                    {
+                        to_set = Some((m, *target));
+                    }
+                });
+            }
+        } else {
+            self.target = None;
+        }
+
+        let mut to_remove = None;
+        for (k, m) in self.items.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("❌").on_hover_text("Remove this measurement").clicked() {
+                    to_remove = Some(k);
+                }
+                match mol {
+                    Some(mol) => ui.label(m.format(mol)),
+                    None => ui.label(m.name()),
+                };
+            });
+        }
+        if let Some(k) = to_remove {
+            self.items.remove(k);
+        }
+        to_set
+    }
+}
+// e9d5a048 ends here
+
+// [[file:../../bevy.note::7f3a6b12][7f3a6b12]]
+/// The color of measurement annotation
+const MARK_COLOR: Color = Color::ORANGE;
+
+/// Redraw measurement marks on changes of measurements, selection or
+/// molecule.
+pub fn update_measurement_marks(
+    mut commands: Commands,
+    state: Res<State>,
+    selected_atoms: Res<SelectedAtoms>,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut assets: MoleculeAssets,
+    mark_query: Query<Entity, With<MeasurementMark>>,
+) {
+    if !(state.is_changed() || selected_atoms.is_changed() || traj.is_changed() || current_frame.is_changed()) {
+        return;
+    }
+
+    for entity in mark_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+    let MoleculeAssets {
+        meshes,
+        materials,
+        cache,
+    } = &mut assets;
+    for m in annotated(&state, &selected_atoms.0) {
+        if let Some((lines, _)) = annotation(&m, mol) {
+            for line in lines {
+                let points: Vec<_> = line.iter().map(|p| p.as_vec3()).collect();
+                for dash in crate::base::create_dashed_line(&points, 0.02, MARK_COLOR, meshes, materials, cache) {
+                    commands.spawn((dash, MeasurementMark));
+                }
+            }
+        }
+    }
+}
+
+/// Draw text labels of measurements by projecting 3D positions to the
+/// screen.
+pub fn draw_measurement_labels(
+    mut contexts: EguiContexts,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    state: Res<State>,
+    selected_atoms: Res<SelectedAtoms>,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+) {
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let ctx = contexts.ctx_mut();
+    let screen_height = ctx.screen_rect().height();
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("measurement_labels"),
+    ));
+    let [r, g, b, _] = MARK_COLOR.as_rgba_u32().to_le_bytes();
+    let color = egui::Color32::from_rgb(r, g, b);
+    for m in annotated(&state, &selected_atoms.0) {
+        let Some((_, position)) = annotation(&m, mol) else {
+            continue;
+        };
+        let Some(value) = m.evaluate(mol) else {
+            continue;
+        };
+        if let Some(p) = camera.world_to_viewport(camera_transform, position.as_vec3()) {
+            let text = match m {
+                Measurement::Distance(_) => format!("{value:.3} {}", m.unit()),
+                _ => format!("{value:.1}{}", m.unit()),
+            };
+            // viewport origin is at bottom left
+            let pos = egui::Pos2::new(p.x, screen_height - p.y);
+            painter.text(
+                pos,
+                egui::Align2::CENTER_BOTTOM,
+                text,
+                egui::FontId::proportional(14.0),
+                color,
+            );
+        }
+    }
+}
+// 7f3a6b12 ends here
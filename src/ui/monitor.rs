@@ -0,0 +1,259 @@
+// [[file:../../bevy.note::5e7b20c9][5e7b20c9]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::plot::{Line, Plot, PlotPoints, VLine};
+use egui::Ui;
+use gut::prelude::Result;
+
+use super::measurement::Measurement;
+use crate::base::CurrentFrame;
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// 5e7b20c9 ends here
+
+// [[file:../../bevy.note::a93c1f64][a93c1f64]]
+/// The height of each monitor plot
+const PLOT_HEIGHT: f32 = 120.0;
+
+/// A measurement evaluated over all frames in trajectory
+#[derive(Debug, Clone)]
+struct Monitor {
+    measurement: Measurement,
+    /// The value in each frame. None if atoms missing in the frame.
+    values: Vec<Option<f64>>,
+}
+
+impl Monitor {
+    fn new(measurement: Measurement, traj: &MoleculeTrajectory) -> Self {
+        let mut monitor = Self {
+            measurement,
+            values: vec![],
+        };
+        monitor.update(traj);
+        monitor
+    }
+
+    /// Evaluate values for all frames in `traj`.
+    fn update(&mut self, traj: &MoleculeTrajectory) {
+        self.values = traj
+            .get_molecules()
+            .iter()
+            .map(|mol| self.measurement.evaluate(mol))
+            .collect();
+    }
+
+    /// Evaluate value for frame `index` in `traj` only.
+    fn update_frame(&mut self, traj: &MoleculeTrajectory, index: usize) {
+        if let (Some(mol), Some(value)) = (traj.get_molecules().get(index), self.values.get_mut(index)) {
+            *value = self.measurement.evaluate(mol);
+        }
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct State {
+    monitors: Vec<Monitor>,
+    message: String,
+    /// The revision of edit history seen last time
+    revision: usize,
+    /// Frames changed since last evaluation
+    outdated_frames: Vec<usize>,
+    /// All frames may be changed since last evaluation
+    all_outdated: bool,
+}
+
+impl State {
+    /// Mark `frame` as changed, or all frames if None.
+    fn mark_outdated(&mut self, frame: Option<usize>) {
+        match frame {
+            Some(i) if !self.outdated_frames.contains(&i) => self.outdated_frames.push(i),
+            Some(_) => (),
+            None => self.all_outdated = true,
+        }
+    }
+
+    /// Re-evaluate monitors in changed frames of `traj`.
+    fn update_outdated(&mut self, traj: &MoleculeTrajectory) {
+        let nframes = traj.nframes();
+        for monitor in self.monitors.iter_mut() {
+            // frames inserted or removed
+            if self.all_outdated || monitor.values.len() != nframes {
+                monitor.update(traj);
+            } else {
+                for &i in &self.outdated_frames {
+                    monitor.update_frame(traj, i);
+                }
+            }
+        }
+        self.outdated_frames.clear();
+        self.all_outdated = false;
+    }
+
+    /// Add monitor for `measurement` if not present yet.
+    fn add(&mut self, measurement: Measurement, traj: &MoleculeTrajectory) {
+        if self.monitors.iter().all(|m| m.measurement != measurement) {
+            self.monitors.push(Monitor::new(measurement, traj));
+        }
+    }
+
+    /// Write values of all monitors into CSV file in `path`, one frame
+    /// per row.
+    fn export_csv(&self, path: &std::path::Path) -> Result<()> {
+        let mut lines = vec![];
+        let header: Vec<_> = std::iter::once("frame".to_owned())
+            .chain(
+                self.monitors
+                    .iter()
+                    .map(|m| format!("{} ({})", m.measurement.name(), m.measurement.unit())),
+            )
+            .collect();
+        lines.push(header.join(","));
+        let nframes = self.monitors.iter().map(|m| m.values.len()).max().unwrap_or(0);
+        for i in 0..nframes {
+            let row: Vec<_> = std::iter::once(i.to_string())
+                .chain(self.monitors.iter().map(|m| match m.values.get(i) {
+                    Some(Some(v)) => format!("{v}"),
+                    _ => String::new(),
+                }))
+                .collect();
+            lines.push(row.join(","));
+        }
+        gut::fs::write_to_file(path, &(lines.join("\n") + "\n"))
+    }
+
+    /// Show monitor plots. Return the frame clicked in plot.
+    fn show(
+        &mut self,
+        ui: &mut Ui,
+        traj: &MoleculeTrajectory,
+        current_frame: Option<usize>,
+        selected_atoms: &[usize],
+        measurements: &[Measurement],
+    ) -> Option<usize> {
+        ui.horizontal(|ui| {
+            let current = Measurement::from_atoms(selected_atoms);
+            if ui
+                .add_enabled(current.is_some(), egui::Button::new("Add selected"))
+                .on_hover_text("Monitor distance, angle or dihedral of 2, 3 or 4 selected atoms")
+                .clicked()
+            {
+                if let Some(m) = current {
+                    self.add(m, traj);
+                }
+            }
+            if ui
+                .add_enabled(!measurements.is_empty(), egui::Button::new("Add measurements"))
+                .on_hover_text("Monitor all measurements in side panel")
+                .clicked()
+            {
+                for &m in measurements {
+                    self.add(m, traj);
+                }
+            }
+            if ui
+                .add_enabled(!self.monitors.is_empty(), egui::Button::new("Export CSV…"))
+                .clicked()
+            {
+                if let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv"]).save_file() {
+                    match self.export_csv(&path) {
+                        Ok(_) => self.message = format!("Monitors exported to {path:?}"),
+                        Err(err) => self.message = format!("{err:?}"),
+                    }
+                }
+            }
+            ui.label(&self.message);
+        });
+
+        let mut clicked_frame = None;
+        let mut to_remove = None;
+        for (k, monitor) in self.monitors.iter().enumerate() {
+            let m = &monitor.measurement;
+            ui.horizontal(|ui| {
+                if ui.small_button("❌").on_hover_text("Remove this monitor").clicked() {
+                    to_remove = Some(k);
+                }
+                let value = current_frame.and_then(|i| monitor.values.get(i).copied().flatten());
+                match value {
+                    Some(v) => ui.label(format!("{} = {v:.4} {}", m.name(), m.unit())),
+                    None => ui.label(m.name()),
+                };
+            });
+            let points: PlotPoints = monitor
+                .values
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| v.map(|v| [i as f64, v]))
+                .collect();
+            let response = Plot::new(("monitor", m.name()))
+                .height(PLOT_HEIGHT)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(points).name(m.name()));
+                    if let Some(i) = current_frame {
+                        plot_ui.vline(VLine::new(i as f64).color(egui::Color32::RED));
+                    }
+                    plot_ui.pointer_coordinate()
+                });
+            if response.response.clicked() {
+                if let Some(p) = response.inner {
+                    clicked_frame = Some(p.x.round().max(0.0) as usize);
+                }
+            }
+        }
+        if let Some(k) = to_remove {
+            self.monitors.remove(k);
+        }
+        clicked_frame
+    }
+}
+// a93c1f64 ends here
+
+// [[file:../../bevy.note::17e4d6ab][17e4d6ab]]
+/// Show measurement monitors over trajectory frames in bottom panel.
+pub fn monitor_panel_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    (traj, history): (Res<MoleculeTrajectory>, Res<super::history::History>),
+    mut current_frame: ResMut<CurrentFrame>,
+    selected_atoms: Res<SelectedAtoms>,
+    measurements: Res<super::measurement::State>,
+) {
+    // find frames changed from edit history: the frame of the only new
+    // edit, or the frame edited again by the last one, e.g. on dragging
+    // atoms. Otherwise all frames may be changed, e.g. on loading.
+    let (revision, last_frame) = history.last_edit();
+    if traj.is_changed() || revision != state.revision {
+        let frame = last_frame.filter(|_| revision <= state.revision + 1);
+        state.mark_outdated(frame);
+        state.revision = revision;
+    }
+    if !ui_state.monitor_panel_open {
+        return;
+    }
+    // re-evaluate monitors only when displayed
+    state.update_outdated(&traj);
+
+    let ctx = contexts.ctx_mut();
+    let iframe = traj.get_current_frame_index(&current_frame);
+    let mut clicked_frame = None;
+    egui::TopBottomPanel::bottom("monitor_panel")
+        .resizable(true)
+        .default_height(200.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("Monitors");
+                if ui.small_button("❌").on_hover_text("Close monitors").clicked() {
+                    ui_state.monitor_panel_open = false;
+                }
+            });
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                clicked_frame = state.show(ui, &traj, iframe, &selected_atoms.0, &measurements.items);
+            });
+        });
+    if let Some(i) = clicked_frame {
+        if i < traj.nframes() {
+            current_frame.0 = i as isize;
+        }
+    }
+}
+// 17e4d6ab ends here
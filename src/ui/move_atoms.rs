@@ -0,0 +1,194 @@
+// [[file:../../bevy.note::3c8e0f5a][3c8e0f5a]]
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+use gchemol::Molecule;
+
+use crate::arcball::PanOrbitCamera;
+use crate::base::{AtomIndex, CurrentFrame};
+use crate::molecule::MoleculeTrajectory;
+// 3c8e0f5a ends here
+
+// [[file:../../bevy.note::b0f6d4e2][b0f6d4e2]]
+/// Atoms being dragged in the screen plane
+#[derive(Debug, Clone)]
+struct Dragging {
+    /// Atoms to be moved with their positions at drag start
+    atoms: Vec<(usize, Vec3)>,
+    /// The point on the dragging plane at drag start
+    start: Vec3,
+    /// The normal of dragging plane, which is parallel to the screen
+    normal: Vec3,
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct State {
+    dragging: Option<Dragging>,
+    /// Camera control has been disabled on pressing an atom
+    camera_disabled: bool,
+}
+
+/// Return the intersection of the dragging plane through `point` with
+/// the ray casted from pointer `position` in viewport.
+fn pointer_on_plane(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    position: Vec2,
+    point: Vec3,
+    normal: Vec3,
+) -> Option<Vec3> {
+    let ray = camera.viewport_to_world(camera_transform, position)?;
+    let denom = ray.direction.dot(normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+    let t = (point - ray.origin).dot(normal) / denom;
+    Some(ray.origin + ray.direction * t)
+}
+
+/// Recompute bonds of `atoms` in `mol` without periodic images as on
+/// loading. Bonds between other atoms are kept together with their
+/// kinds.
+pub fn rebond_atoms(mol: &mut Molecule, atoms: &[usize]) {
+    let mut guessed = mol.clone();
+    guessed.unbuild_crystal();
+    guessed.rebond();
+    for &i in atoms {
+        let connected: Vec<_> = mol.connected(i).collect();
+        for j in connected {
+            if !guessed.has_bond(i, j) {
+                mol.remove_bond(i, j);
+            }
+        }
+        for j in guessed.connected(i) {
+            if !mol.has_bond(i, j) {
+                mol.add_bond(i, j, guessed.get_bond(i, j).expect("guessed bond").clone());
+            }
+        }
+    }
+}
+// b0f6d4e2 ends here
+
+// [[file:../../bevy.note::e15a7c39][e15a7c39]]
+/// Keep selection on pressing an atom in move mode, so that all
+/// selected atoms can be dragged together.
+pub fn keep_selection_in_move_mode(
+    mut commands: Commands,
+    ui_state: Res<super::UiState>,
+    atoms_query: Query<Entity, (With<AtomIndex>, Without<NoDeselect>)>,
+    no_deselect_query: Query<Entity, (With<AtomIndex>, With<NoDeselect>)>,
+) {
+    if ui_state.move_atoms_enabled {
+        for entity in atoms_query.iter() {
+            commands.entity(entity).insert(NoDeselect);
+        }
+    } else {
+        for entity in no_deselect_query.iter() {
+            commands.entity(entity).remove::<NoDeselect>();
+        }
+    }
+}
+
+/// Pointer events for dragging atoms
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct PointerEvents<'w, 's> {
+    down: EventReader<'w, 's, Pointer<Down>>,
+    drag_start: EventReader<'w, 's, Pointer<DragStart>>,
+    drag: EventReader<'w, 's, Pointer<Drag>>,
+    drag_end: EventReader<'w, 's, Pointer<DragEnd>>,
+}
+
+/// Drag atoms in the screen plane. Dragging a selected atom moves all
+/// selected atoms. Bonds of moved atoms will be recomputed on release.
+pub fn drag_atoms(
+    mut state: ResMut<State>,
+    mut events: PointerEvents,
+    mouse_input: Res<Input<MouseButton>>,
+    atoms_query: Query<(&AtomIndex, &PickSelection)>,
+    mut camera_query: Query<(&Camera, &GlobalTransform, &mut PanOrbitCamera)>,
+    (mut traj, mut history): (ResMut<MoleculeTrajectory>, ResMut<super::history::History>),
+    current_frame: Res<CurrentFrame>,
+) {
+    let Ok((camera, camera_transform, mut pan_orbit)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    // suppress camera orbiting once an atom pressed
+    for event in events.down.iter() {
+        if event.button == PointerButton::Primary && atoms_query.contains(event.target) {
+            pan_orbit.enabled = false;
+            state.camera_disabled = true;
+        }
+    }
+
+    for event in events.drag_start.iter() {
+        if event.button != PointerButton::Primary {
+            continue;
+        }
+        let Ok((&AtomIndex(sn), selection)) = atoms_query.get(event.target) else {
+            continue;
+        };
+        let Some(mol) = traj.get_current_molecule(&current_frame) else {
+            continue;
+        };
+        // move all selected atoms if the dragged one is selected
+        let atoms: Vec<_> = if selection.is_selected {
+            atoms_query
+                .iter()
+                .filter_map(|(AtomIndex(i), s)| s.is_selected.then_some(*i))
+                .collect()
+        } else {
+            vec![sn]
+        };
+        let atoms: Vec<_> = atoms
+            .into_iter()
+            .filter_map(|i| {
+                let p = mol.get_atom(i)?.position().map(|x| x as f32);
+                Some((i, Vec3::from(p)))
+            })
+            .collect();
+        let Some(&(_, point)) = atoms.iter().find(|(i, _)| *i == sn) else {
+            continue;
+        };
+        let normal = camera_transform.forward();
+        if let Some(start) = pointer_on_plane(camera, camera_transform, event.pointer_location.position, point, normal) {
+            history.record_current_frame("Move atoms", &traj, &current_frame);
+            state.dragging = Some(Dragging { atoms, start, normal });
+        }
+    }
+
+    if let Some(dragging) = &state.dragging {
+        // only the last drag event matters
+        if let Some(event) = events.drag.iter().filter(|e| e.button == PointerButton::Primary).last() {
+            if let Some(p) = pointer_on_plane(
+                camera,
+                camera_transform,
+                event.pointer_location.position,
+                dragging.start,
+                dragging.normal,
+            ) {
+                let delta = p - dragging.start;
+                if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+                    for &(i, pi) in &dragging.atoms {
+                        mol.set_position(i, (pi + delta).as_dvec3().to_array());
+                    }
+                }
+            }
+        }
+    }
+
+    let drag_ended = events.drag_end.iter().any(|e| e.button == PointerButton::Primary);
+    if drag_ended {
+        if let Some(dragging) = state.dragging.take() {
+            if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+                let atoms: Vec<_> = dragging.atoms.iter().map(|&(i, _)| i).collect();
+                rebond_atoms(mol, &atoms);
+            }
+        }
+    }
+
+    if mouse_input.just_released(MouseButton::Left) && state.camera_disabled {
+        pan_orbit.enabled = true;
+        state.camera_disabled = false;
+    }
+}
+// e15a7c39 ends here
@@ -0,0 +1,120 @@
+// [[file:../../bevy.note::6f2d8b1e][6f2d8b1e]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::{Color32, Pos2, Stroke};
+
+use crate::arcball::PanOrbitCamera;
+use crate::base::CurrentFrame;
+use crate::molecule::MoleculeTrajectory;
+// 6f2d8b1e ends here
+
+// [[file:../../bevy.note::b85c0e3a][b85c0e3a]]
+/// The length of axes of the orientation triad in pixels
+const AXIS_LENGTH: f32 = 40.0;
+
+/// Return axes to be drawn as triad: direction, label and color. Use
+/// cell vectors if `lattice` is present.
+fn triad_axes(lattice: Option<&gchemol::Lattice>) -> [(Vec3, &'static str, Color32); 3] {
+    if let Some(lat) = lattice {
+        // same colors as cell edges
+        let [va, vb, vc] = lat.vectors().map(|v| crate::base::as_vec3(v).normalize_or_zero());
+        [
+            (va, "a", Color32::RED),
+            (vb, "b", Color32::YELLOW),
+            (vc, "c", Color32::LIGHT_BLUE),
+        ]
+    } else {
+        [
+            (Vec3::X, "x", Color32::RED),
+            (Vec3::Y, "y", Color32::GREEN),
+            (Vec3::Z, "z", Color32::LIGHT_BLUE),
+        ]
+    }
+}
+
+/// Draw the orientation axes in the bottom left corner of the 3D view,
+/// which rotates with the camera.
+fn draw_orientation_axes(painter: &egui::Painter, rect: egui::Rect, rotation: Quat, lattice: Option<&gchemol::Lattice>) {
+    let origin = rect.left_bottom() + egui::vec2(AXIS_LENGTH + 20.0, -AXIS_LENGTH - 20.0);
+    // project axes into camera space
+    let mut axes: Vec<_> = triad_axes(lattice)
+        .into_iter()
+        .map(|(v, label, color)| (rotation.inverse() * v, label, color))
+        .collect();
+    // draw far axes first
+    axes.sort_by(|a, b| a.0.z.total_cmp(&b.0.z));
+    for (v, label, color) in axes {
+        let end = origin + egui::vec2(v.x, -v.y) * AXIS_LENGTH;
+        painter.line_segment([origin, end], Stroke::new(2.5, color));
+        let text_pos = origin + egui::vec2(v.x, -v.y) * (AXIS_LENGTH + 10.0);
+        painter.text(
+            text_pos,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::proportional(14.0),
+            color,
+        );
+    }
+}
+
+/// Draw text labels "O", "a", "b", "c" at the origin and cell vectors of `lat`.
+fn draw_lattice_labels(painter: &egui::Painter, camera: &Camera, camera_transform: &GlobalTransform, lat: &gchemol::Lattice) {
+    let screen_height = painter.ctx().screen_rect().height();
+    let points = [
+        ([0.0, 0.0, 0.0], "O"),
+        ([1.0, 0.0, 0.0], "a"),
+        ([0.0, 1.0, 0.0], "b"),
+        ([0.0, 0.0, 1.0], "c"),
+    ];
+    for (frac, label) in points {
+        let position = crate::base::as_vec3(lat.to_cart(frac));
+        if let Some(p) = camera.world_to_viewport(camera_transform, position) {
+            // viewport origin is at bottom left
+            let pos = Pos2::new(p.x, screen_height - p.y);
+            painter.text(
+                pos,
+                egui::Align2::LEFT_BOTTOM,
+                label,
+                egui::FontId::proportional(16.0),
+                Color32::WHITE,
+            );
+        }
+    }
+}
+// b85c0e3a ends here
+
+// [[file:../../bevy.note::2c7a9f53][2c7a9f53]]
+/// Draw orientation axes and lattice labels over the 3D view.
+pub fn orientation_overlay_system(
+    ui_state: Res<super::UiState>,
+    mut contexts: EguiContexts,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+) {
+    if !ui_state.orientation_axes_visible && !ui_state.lattice_labels_visible {
+        return;
+    }
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let lattice = traj.get_current_molecule(&current_frame).and_then(|mol| mol.lattice.as_ref());
+    let ctx = contexts.ctx_mut();
+    // the area of 3D view not covered by panels
+    let rect = ctx.available_rect();
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("orientation_overlay"),
+    ));
+    if ui_state.orientation_axes_visible {
+        let (_, rotation, _) = camera_transform.to_scale_rotation_translation();
+        draw_orientation_axes(&painter, rect, rotation, lattice);
+    }
+    if ui_state.lattice_labels_visible {
+        if let Some(lat) = lattice {
+            draw_lattice_labels(&painter, camera, camera_transform, lat);
+        }
+    }
+}
+// 2c7a9f53 ends here
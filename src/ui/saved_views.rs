@@ -0,0 +1,262 @@
+// [[file:../../bevy.note::c14e7a3b][c14e7a3b]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+use gut::prelude::{Configure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::arcball::PanOrbitCamera;
+use crate::molecule::ProjectionMode;
+// c14e7a3b ends here
+
+// [[file:../../bevy.note::8e5f2d90][8e5f2d90]]
+/// The camera state saved for restoring later. The camera orientation is
+/// stored as rotation, as `PanOrbitCamera` applies orbit motion to the
+/// camera transform incrementally.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SavedView {
+    focus: [f32; 3],
+    radius: f32,
+    rotation: [f32; 4],
+    #[serde(default)]
+    orthographic: bool,
+}
+
+/// Named camera views
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Resource)]
+pub struct SavedViews {
+    pub views: BTreeMap<String, SavedView>,
+}
+
+impl SavedView {
+    fn new(pan_orbit: &PanOrbitCamera, transform: &Transform, mode: ProjectionMode) -> Self {
+        Self {
+            focus: pan_orbit.focus.to_array(),
+            radius: pan_orbit.radius,
+            rotation: transform.rotation.to_array(),
+            orthographic: mode == ProjectionMode::Orthographic,
+        }
+    }
+
+    /// Return the camera transform of this view.
+    fn transform(&self) -> Transform {
+        let rotation = Quat::from_array(self.rotation).normalize();
+        let focus = Vec3::from(self.focus);
+        Transform::from_translation(focus + rotation * Vec3::Z * self.radius).with_rotation(rotation)
+    }
+}
+
+impl SavedViews {
+    /// The default path for saving views in user config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        crate::base::config_dir().map(|d| d.join("views.json"))
+    }
+
+    /// Load views from JSON file in `path`.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let s = gut::fs::read_file(path)?;
+        Self::from_json(&s)
+    }
+
+    /// Save views into JSON file in `path`.
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        let s = self.to_json()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        gut::fs::write_to_file(path, &s)
+    }
+
+    /// Load views saved in last session if any.
+    pub fn load() -> Self {
+        let Some(path) = Self::default_path().filter(|p| p.exists()) else {
+            return Self::default();
+        };
+        match Self::from_file(&path) {
+            Ok(views) => {
+                info!("Loaded camera views from {path:?}");
+                views
+            }
+            Err(err) => {
+                error!("Failed to load camera views: {err:?}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Save views for next session.
+    fn save(&self) -> Result<()> {
+        if let Some(path) = Self::default_path() {
+            self.to_file(&path)?;
+        }
+        Ok(())
+    }
+}
+// 8e5f2d90 ends here
+
+// [[file:../../bevy.note::3b6d9a27][3b6d9a27]]
+#[derive(Debug, Default, Resource)]
+pub struct State {
+    /// The name for saving current view
+    new_name: String,
+    message: String,
+}
+
+/// Actions on saved views
+enum Action {
+    Save(String),
+    Restore(String),
+    Delete(String),
+    Export,
+    Import,
+}
+
+impl State {
+    fn show(&mut self, ui: &mut Ui, views: &SavedViews) -> Option<Action> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("view name"));
+            let name = self.new_name.trim();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("Save current view"))
+                .on_hover_text("Save current view with the name, overwriting existing one")
+                .clicked()
+            {
+                action = Some(Action::Save(name.to_owned()));
+                self.new_name.clear();
+            }
+        });
+        ui.separator();
+
+        if views.views.is_empty() {
+            ui.label("No saved views");
+        }
+        egui::Grid::new("saved_views_grid").striped(true).show(ui, |ui| {
+            for (name, view) in views.views.iter() {
+                ui.label(name);
+                ui.label(if view.orthographic { "orthographic" } else { "perspective" });
+                if ui.button("Restore").clicked() {
+                    action = Some(Action::Restore(name.to_owned()));
+                }
+                if ui.button("❌").on_hover_text("Delete this view").clicked() {
+                    action = Some(Action::Delete(name.to_owned()));
+                }
+                ui.end_row();
+            }
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Export…").on_hover_text("Export all views as JSON").clicked() {
+                action = Some(Action::Export);
+            }
+            if ui.button("Import…").on_hover_text("Import views from JSON file").clicked() {
+                action = Some(Action::Import);
+            }
+        });
+        if !self.message.is_empty() {
+            ui.label(&self.message);
+        }
+        action
+    }
+}
+// 3b6d9a27 ends here
+
+// [[file:../../bevy.note::f70c85e1][f70c85e1]]
+/// Restore camera of `pan_orbit` to `view` smoothly.
+fn restore_view(
+    commands: &mut Commands,
+    entity: Entity,
+    transform: &Transform,
+    pan_orbit: &mut PanOrbitCamera,
+    projection: &mut Projection,
+    view: &SavedView,
+) {
+    let rotation = view.transform().rotation;
+    let transition = super::camera_view::CameraTransition::new(transform, pan_orbit.focus, pan_orbit.radius, rotation);
+    commands.entity(entity).insert(transition);
+    pan_orbit.focus = view.focus.into();
+    pan_orbit.radius = view.radius;
+    // the orthographic scale follows orbit radius
+    if let Projection::Orthographic(p) = projection {
+        p.scale = view.radius;
+    }
+}
+
+pub fn saved_views_window_system(
+    mut commands: Commands,
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    mut views: ResMut<SavedViews>,
+    mut projection_mode: ResMut<ProjectionMode>,
+    mut camera_query: Query<(Entity, &Transform, &mut PanOrbitCamera, &mut Projection)>,
+) {
+    if !ui_state.saved_views_window_open {
+        return;
+    }
+
+    let ctx = contexts.ctx_mut();
+    let mut action = None;
+    egui::Window::new("Saved views")
+        .open(&mut ui_state.saved_views_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            action = state.show(ui, &views);
+        });
+
+    let Some(action) = action else {
+        return;
+    };
+    let Ok((entity, transform, mut pan_orbit, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    match action {
+        Action::Save(name) => {
+            let view = SavedView::new(&pan_orbit, transform, *projection_mode);
+            views.views.insert(name, view);
+        }
+        Action::Restore(name) => {
+            if let Some(view) = views.views.get(&name) {
+                restore_view(&mut commands, entity, transform, &mut pan_orbit, &mut projection, view);
+                let mode = if view.orthographic {
+                    ProjectionMode::Orthographic
+                } else {
+                    ProjectionMode::Perspective
+                };
+                projection_mode.set_if_neq(mode);
+            }
+            return;
+        }
+        Action::Delete(name) => {
+            views.views.remove(&name);
+        }
+        Action::Export => {
+            if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).save_file() {
+                match views.to_file(&path) {
+                    Ok(_) => state.message = format!("Views exported to {path:?}"),
+                    Err(err) => state.message = format!("{err:?}"),
+                }
+            }
+            return;
+        }
+        Action::Import => {
+            if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                match SavedViews::from_file(&path) {
+                    Ok(imported) => {
+                        state.message = format!("{} views imported from {path:?}", imported.views.len());
+                        views.views.extend(imported.views);
+                    }
+                    Err(err) => state.message = format!("{err:?}"),
+                }
+            }
+        }
+    }
+    // persist for next session
+    if let Err(err) = views.save() {
+        state.message = format!("Failed to save views: {err:?}");
+    }
+}
+// f70c85e1 ends here
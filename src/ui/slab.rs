@@ -0,0 +1,447 @@
+// [[file:../../bevy.note::7b1d4e93][7b1d4e93]]
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+use gchemol::{Lattice, Molecule};
+
+use crate::base::CurrentFrame;
+use crate::molecule::MoleculeTrajectory;
+// 7b1d4e93 ends here
+
+// [[file:../../bevy.note::c2f85a06][c2f85a06]]
+/// Atoms within this distance in Å along surface normal belong to the
+/// same atomic layer
+const LAYER_TOLERANCE: f64 = 0.1;
+
+/// Floor division as in Python, required by `ext_gcd`.
+fn div_floor(a: i32, b: i32) -> i32 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Return (x, y) satisfying a x + b y = gcd(a, b).
+fn ext_gcd(a: i32, b: i32) -> (i32, i32) {
+    if b == 0 {
+        (1, 0)
+    } else if a - b * div_floor(a, b) == 0 {
+        (0, 1)
+    } else {
+        let (x, y) = ext_gcd(b, a - b * div_floor(a, b));
+        (y, x - y * div_floor(a, b))
+    }
+}
+
+fn lattice_vectors(lat: &Lattice) -> [DVec3; 3] {
+    lat.vectors().map(|v| DVec3::from(<[f64; 3]>::from(v)))
+}
+
+/// Return the transformation matrix of a surface cell for Miller
+/// indices `hkl` of `lat`: the first two rows span the (hkl) plane,
+/// and the third row completes a primitive cell in right-handed order.
+/// The in-plane vectors are chosen to be as orthogonal as possible,
+/// following the algorithm used by ASE.
+fn surface_basis(lat: &Lattice, hkl: [i32; 3]) -> Result<[[i32; 3]; 3], String> {
+    let n = gcd(gcd(hkl[0], hkl[1]), hkl[2]);
+    if n == 0 {
+        return Err("Invalid Miller indices (0 0 0)".into());
+    }
+    let [h, k, l] = hkl.map(|x| x / n);
+    let [a1, a2, a3] = lattice_vectors(lat);
+
+    let mut basis = match (h, k, l) {
+        (0, 0, _) => [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        (0, _, 0) => [[0, 0, 1], [1, 0, 0], [0, 1, 0]],
+        (_, 0, 0) => [[0, 1, 0], [0, 0, 1], [1, 0, 0]],
+        _ => {
+            let (mut p, mut q) = ext_gcd(k, l);
+            let (hf, kf, lf) = (h as f64, k as f64, l as f64);
+            let v = lf * a2 - kf * a3;
+            let k1 = (p as f64 * (kf * a1 - hf * a2) + q as f64 * (lf * a1 - hf * a3)).dot(v);
+            let k2 = (lf * (kf * a1 - hf * a2) - kf * (lf * a1 - hf * a3)).dot(v);
+            if k2.abs() > 1e-10 {
+                // the most orthogonal one in c1 + i c2
+                let i = -(k1 / k2).round() as i32;
+                p += i * l;
+                q -= i * k;
+            }
+            let (a, b) = ext_gcd(p * k + q * l, h);
+            let g = gcd(l, k);
+            [[p * k + q * l, -p * h, -q * h], [0, l / g, -k / g], [b, a * p, a * q]]
+        }
+    };
+
+    // flip the out-of-plane vector for right-handed cell
+    let cart = |row: [i32; 3]| row[0] as f64 * a1 + row[1] as f64 * a2 + row[2] as f64 * a3;
+    if cart(basis[0]).cross(cart(basis[1])).dot(cart(basis[2])) < 0.0 {
+        basis[2] = basis[2].map(|x| -x);
+    }
+    Ok(basis)
+}
+
+/// Return the surface cell of `bulk` for Miller indices `hkl` in
+/// standard orientation: a along x, b in xy plane, and the surface
+/// normal along z. The third lattice vector is kept as in bulk for the
+/// right stacking of atomic layers. Atoms are wrapped into the cell.
+fn oriented_surface_cell(bulk: &Molecule, hkl: [i32; 3]) -> Result<Molecule, String> {
+    let lat = bulk.get_lattice().ok_or("No lattice in current molecule")?;
+    let basis = surface_basis(lat, hkl)?;
+    let mut cell = super::supercell::build_supercell(bulk, &basis)?;
+    let lat = *cell.get_lattice().expect("surface cell");
+
+    let [a1, a2, a3] = lattice_vectors(&lat);
+    let x = a1.normalize();
+    let z = a1.cross(a2).normalize();
+    let y = z.cross(x);
+    let rotate = |v: DVec3| [v.dot(x), v.dot(y), v.dot(z)];
+    let new_lat = Lattice::new([rotate(a1), rotate(a2), rotate(a3)]);
+    // avoid splitting an atomic layer on the cell boundary
+    let eps = LAYER_TOLERANCE / a3.dot(z);
+    let positions: Vec<[f64; 3]> = cell
+        .positions()
+        .map(|p| {
+            let f = lat.to_frac(p);
+            let f = [f[0].rem_euclid(1.0), f[1].rem_euclid(1.0), (f[2] + eps).rem_euclid(1.0) - eps];
+            new_lat.to_cart(f).into()
+        })
+        .collect();
+    cell.set_positions(positions);
+    cell.set_lattice(new_lat);
+    Ok(cell)
+}
+
+/// Group atoms of `mol` into atomic layers by their z coordinates.
+/// Return (z, atoms) of layers from bottom to top.
+fn atomic_layers(mol: &Molecule) -> Vec<(f64, Vec<usize>)> {
+    let mut atoms: Vec<(usize, f64)> = mol.atoms().map(|(i, a)| (i, a.position()[2])).collect();
+    atoms.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let mut layers: Vec<(f64, Vec<usize>)> = vec![];
+    let mut last_z = f64::MIN;
+    for (i, z) in atoms {
+        match layers.last_mut() {
+            Some((_, layer)) if z - last_z < LAYER_TOLERANCE => layer.push(i),
+            _ => layers.push((z, vec![i])),
+        }
+        last_z = z;
+    }
+    layers
+}
+
+/// Return the chemical formula of `atoms` in `mol`, e.g. "Ti O2".
+fn layer_formula(mol: &Molecule, atoms: &[usize]) -> String {
+    let mut counts: Vec<(String, usize)> = vec![];
+    for &i in atoms {
+        let symbol = mol.get_atom_unchecked(i).symbol().to_string();
+        match counts.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((symbol, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(s, n)| if *n == 1 { s.clone() } else { format!("{s}{n}") })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Return the possible terminations of (hkl) surface of `bulk`,
+/// described by the formula of top atomic layer.
+fn terminations(bulk: &Molecule, hkl: [i32; 3]) -> Result<Vec<String>, String> {
+    let cell = oriented_surface_cell(bulk, hkl)?;
+    let terminations = atomic_layers(&cell)
+        .iter()
+        .map(|(z, atoms)| format!("{} (z = {z:.3} Å)", layer_formula(&cell, atoms)))
+        .collect();
+    Ok(terminations)
+}
+
+/// Parameters for building surface slab
+#[derive(Debug, Clone)]
+struct SlabParams {
+    /// Miller indices of the surface
+    hkl: [i32; 3],
+    /// The number of surface cells stacked along surface normal
+    layers: usize,
+    /// Vacuum thickness in Å
+    vacuum: f64,
+    /// The atomic layer in surface cell exposed on top
+    termination: usize,
+    /// The number of bottom atomic layers to be frozen
+    freeze_layers: usize,
+}
+
+/// Build surface slab from `bulk` with `params`. Surface cells are
+/// stacked along the third vector of surface cell as in bulk, and the
+/// slab is centered in a cell with c normal to the surface.
+fn build_slab(bulk: &Molecule, params: &SlabParams) -> Result<Molecule, String> {
+    let cell = oriented_surface_cell(bulk, params.hkl)?;
+    let lat = *cell.get_lattice().expect("surface cell");
+    let [a1, a2, a3] = lattice_vectors(&lat);
+    let height = a3.z;
+
+    // shift the termination layer to the top of surface cell
+    let layers = atomic_layers(&cell);
+    let (z_top, _) = layers.get(params.termination).ok_or("Invalid termination")?;
+    let eps = LAYER_TOLERANCE / height;
+    let ft = z_top / height;
+    let shifted: Vec<_> = cell
+        .atoms()
+        .map(|(_, atom)| {
+            let mut f = lat.to_frac(atom.position());
+            f[2] = (f[2] - ft - eps).rem_euclid(1.0) + eps;
+            (atom, DVec3::from(<[f64; 3]>::from(lat.to_cart(f))))
+        })
+        .collect();
+
+    // stack surface cells along the bulk lattice vector
+    let zmin = shifted.iter().map(|(_, p)| p.z).fold(f64::MAX, f64::min);
+    let zmax = shifted.iter().map(|(_, p)| p.z).fold(f64::MIN, f64::max) + height * (params.layers - 1) as f64;
+    let c = zmax - zmin + params.vacuum;
+    // replace c with surface normal only at last on adding vacuum
+    let slab_lat = Lattice::new([a1.to_array(), a2.to_array(), [0.0, 0.0, c]]);
+    let offset = DVec3::Z * (params.vacuum / 2.0 - zmin);
+    let [h, k, l] = params.hkl;
+    let mut slab = Molecule::new(&format!("{} ({h} {k} {l}) slab", bulk.title()));
+    let mut sn = 0;
+    for m in 0..params.layers {
+        for (atom, p) in &shifted {
+            let mut atom = (*atom).clone();
+            let p = *p + offset + a3 * m as f64;
+            atom.set_position(slab_lat.wrap(p.to_array()));
+            sn += 1;
+            slab.add_atom(sn, atom);
+        }
+    }
+    if slab.natoms() == 0 {
+        return Err("No atoms in slab".into());
+    }
+
+    let frozen: Vec<usize> = atomic_layers(&slab)
+        .into_iter()
+        .take(params.freeze_layers)
+        .flat_map(|(_, atoms)| atoms)
+        .collect();
+    super::constraints::set_freezing(&mut slab, &frozen, [true; 3]);
+
+    slab.rebond();
+    slab.set_lattice(slab_lat);
+    Ok(slab)
+}
+// c2f85a06 ends here
+
+// [[file:../../bevy.note::4d7e2b90][4d7e2b90]]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gchemol::Atom;
+
+    /// Return conventional cell of fcc metal with lattice constant `a`.
+    fn fcc(a: f64) -> Molecule {
+        let mut mol = Molecule::new("fcc");
+        let lat = Lattice::from_params(a, a, a, 90.0, 90.0, 90.0);
+        for (i, f) in [[0.0, 0.0, 0.0], [0.5, 0.5, 0.0], [0.5, 0.0, 0.5], [0.0, 0.5, 0.5]]
+            .into_iter()
+            .enumerate()
+        {
+            mol.add_atom(i + 1, Atom::new("Cu", lat.to_cart(f)));
+        }
+        mol.set_lattice(lat);
+        mol
+    }
+
+    /// Return true if atomic layers `i` and `j` of `slab` are in the
+    /// same lateral registry.
+    fn same_registry(slab: &Molecule, layers: &[(f64, Vec<usize>)], i: usize, j: usize) -> bool {
+        let lat = slab.get_lattice().unwrap();
+        let position = |sn: usize| DVec3::from(slab.get_atom_unchecked(sn).position());
+        let pi = position(layers[i].1[0]);
+        layers[j].1.iter().any(|&sn| {
+            let mut d = position(sn) - pi;
+            d.z = 0.0;
+            let f = lat.to_frac(d.to_array());
+            (0..2).all(|k| (f[k] - f[k].round()).abs() < 1e-6)
+        })
+    }
+
+    #[test]
+    fn test_fcc111_stacking() {
+        let bulk = fcc(3.61);
+        let params = SlabParams {
+            hkl: [1, 1, 1],
+            layers: 4,
+            vacuum: 10.0,
+            termination: 0,
+            freeze_layers: 0,
+        };
+        let slab = build_slab(&bulk, &params).unwrap();
+        let layers = atomic_layers(&slab);
+        assert_eq!(layers.len(), 4);
+        // interlayer distance
+        let d = layers[1].0 - layers[0].0;
+        assert!((d - 3.61 / 3f64.sqrt()).abs() < 1e-6);
+        // ABCA stacking
+        assert!(!same_registry(&slab, &layers, 0, 1));
+        assert!(!same_registry(&slab, &layers, 0, 2));
+        assert!(!same_registry(&slab, &layers, 1, 2));
+        assert!(same_registry(&slab, &layers, 0, 3));
+        // c normal to surface
+        let [.., c] = lattice_vectors(slab.get_lattice().unwrap());
+        assert!(c.x.abs() < 1e-8 && c.y.abs() < 1e-8);
+    }
+}
+// 4d7e2b90 ends here
+
+// [[file:../../bevy.note::5e09a7c1][5e09a7c1]]
+#[derive(Debug, Resource)]
+pub struct State {
+    params: SlabParams,
+    /// Available terminations for `params.hkl`
+    terminations: Result<Vec<String>, String>,
+    /// The Miller indices which `terminations` were computed for, reset
+    /// on changing current molecule
+    terminations_hkl: Option<[i32; 3]>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            params: SlabParams {
+                hkl: [1, 1, 1],
+                layers: 3,
+                vacuum: 15.0,
+                termination: 0,
+                freeze_layers: 0,
+            },
+            terminations: Ok(vec![]),
+            terminations_hkl: None,
+        }
+    }
+}
+
+impl State {
+    /// Update available terminations for `bulk` if required.
+    fn update_terminations(&mut self, bulk: &Molecule) {
+        if self.terminations_hkl != Some(self.params.hkl) {
+            self.terminations = terminations(bulk, self.params.hkl);
+            self.terminations_hkl = Some(self.params.hkl);
+            let n = self.terminations.as_ref().map_or(0, |t| t.len());
+            if self.params.termination >= n {
+                self.params.termination = 0;
+            }
+        }
+    }
+
+    /// Show slab options. Return true if build button clicked.
+    fn show(&mut self, ui: &mut Ui) -> bool {
+        let params = &mut self.params;
+        egui::Grid::new("slab_params_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Miller indices");
+            ui.horizontal(|ui| {
+                for x in params.hkl.iter_mut() {
+                    ui.add(egui::DragValue::new(x).speed(0.1).clamp_range(-10..=10));
+                }
+            });
+            ui.end_row();
+            ui.label("Layers");
+            ui.add(egui::DragValue::new(&mut params.layers).speed(0.1).clamp_range(1..=20))
+                .on_hover_text("The number of surface cells stacked along surface normal");
+            ui.end_row();
+            ui.label("Vacuum");
+            ui.add(
+                egui::DragValue::new(&mut params.vacuum)
+                    .speed(0.1)
+                    .clamp_range(0.0..=100.0)
+                    .suffix(" Å"),
+            );
+            ui.end_row();
+        });
+
+        let terminations = match &self.terminations {
+            Ok(terminations) => terminations,
+            Err(err) => {
+                ui.label(err.as_str());
+                return false;
+            }
+        };
+        ui.separator();
+        ui.label("Top termination");
+        for (i, termination) in terminations.iter().enumerate() {
+            ui.radio_value(&mut params.termination, i, termination.as_str());
+        }
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Freeze bottom");
+            ui.add(
+                egui::DragValue::new(&mut params.freeze_layers)
+                    .speed(0.1)
+                    .clamp_range(0..=100),
+            );
+            ui.label("atomic layers");
+        });
+        ui.add_enabled(!terminations.is_empty(), egui::Button::new("Build"))
+            .on_hover_text("Load the slab as a new molecule")
+            .clicked()
+    }
+}
+// 5e09a7c1 ends here
+
+// [[file:../../bevy.note::a83f6d2b][a83f6d2b]]
+/// Build surface slab from current bulk structure, and load it as a
+/// new molecule.
+pub fn slab_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut writer: EventWriter<crate::net::StreamEvent>,
+) {
+    // the terminations are outdated, even if bulk changed while the
+    // window is closed
+    if traj.is_changed() || current_frame.is_changed() {
+        state.terminations_hkl = None;
+    }
+    if !ui_state.slab_window_open {
+        return;
+    }
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+    state.update_terminations(mol);
+
+    let ctx = contexts.ctx_mut();
+    let mut build = false;
+    egui::Window::new("Build surface slab")
+        .open(&mut ui_state.slab_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            build = state.show(ui);
+        });
+    if !build {
+        return;
+    }
+
+    ui_state.message = match build_slab(mol, &state.params) {
+        Ok(slab) => {
+            let message = format!("Slab built with {} atoms", slab.natoms());
+            let command = crate::net::RemoteCommand::Load(vec![slab]);
+            writer.send(crate::net::StreamEvent(command));
+            message
+        }
+        Err(err) => err,
+    };
+}
+// a83f6d2b ends here
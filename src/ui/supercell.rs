@@ -0,0 +1,225 @@
+// [[file:../../bevy.note::e7c03a59][e7c03a59]]
+use bevy::math::{DMat3, DVec3};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+use gchemol::{Lattice, Molecule};
+
+use crate::base::CurrentFrame;
+use crate::molecule::{MoleculeTrajectory, SelectedAtoms};
+// e7c03a59 ends here
+
+// [[file:../../bevy.note::18b6f4ad][18b6f4ad]]
+/// Return the determinant of integer transformation `matrix`.
+fn determinant(matrix: &[[i32; 3]; 3]) -> i32 {
+    let [[a, b, c], [d, e, f], [g, h, i]] = *matrix;
+    a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g)
+}
+
+/// Build supercell of `mol` with transformation `matrix`, in which
+/// each row defines a new lattice vector as a combination of the
+/// old ones. Replicated atoms keep their labels and freezing flags.
+pub fn build_supercell(mol: &Molecule, matrix: &[[i32; 3]; 3]) -> Result<Molecule, String> {
+    let lat = mol.get_lattice().ok_or("No lattice in current molecule")?;
+    let det = determinant(matrix);
+    if det == 0 {
+        return Err("Singular transformation matrix".into());
+    }
+
+    let old_vectors: Vec<DVec3> = lat.vectors().iter().map(|v| DVec3::from(<[f64; 3]>::from(*v))).collect();
+    let new_vectors: Vec<[f64; 3]> = matrix
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&old_vectors)
+                .map(|(&n, v)| *v * n as f64)
+                .sum::<DVec3>()
+                .to_array()
+        })
+        .collect();
+    let mut new_lat = Lattice::new([new_vectors[0], new_vectors[1], new_vectors[2]]);
+    new_lat.set_origin(lat.origin());
+
+    // fractional coordinates in old cell to new cell: f' = f P⁻¹, in
+    // column vectors with the transposed matrix
+    let p = DMat3::from_cols_array_2d(&matrix.map(|row| row.map(|x| x as f64)));
+    let p_inv = p.inverse();
+    // translations covering the new cell in old fractional coordinates
+    let corners: Vec<DVec3> = (0..8)
+        .map(|k| DVec3::new((k & 1) as f64, ((k >> 1) & 1) as f64, ((k >> 2) & 1) as f64))
+        .map(|c| p * c)
+        .collect();
+    let lower = corners.iter().fold(DVec3::splat(f64::MAX), |acc, c| acc.min(*c)).floor();
+    let upper = corners.iter().fold(DVec3::splat(f64::MIN), |acc, c| acc.max(*c)).ceil();
+
+    let eps = 1e-8;
+    let mut supercell = Molecule::new(&mol.title());
+    let mut sn = 0;
+    for i in lower.x as i32..upper.x as i32 {
+        for j in lower.y as i32..upper.y as i32 {
+            for k in lower.z as i32..upper.z as i32 {
+                let t = DVec3::new(i as f64, j as f64, k as f64);
+                for (_, atom) in mol.atoms() {
+                    let f = DVec3::from(<[f64; 3]>::from(lat.to_frac(atom.position())));
+                    let f = p_inv * (f - f.floor() + t);
+                    if f.cmpge(DVec3::splat(-eps)).all() && f.cmplt(DVec3::splat(1.0 - eps)).all() {
+                        let mut atom = atom.clone();
+                        atom.set_position(new_lat.to_cart(f.to_array()));
+                        sn += 1;
+                        supercell.add_atom(sn, atom);
+                    }
+                }
+            }
+        }
+    }
+    supercell.rebond();
+    supercell.set_lattice(new_lat);
+    Ok(supercell)
+}
+// 18b6f4ad ends here
+
+// [[file:../../bevy.note::c95d1e38][c95d1e38]]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    /// Repeat cell along a, b, c
+    Repeat,
+    /// General transformation matrix
+    Matrix,
+}
+
+#[derive(Debug, Resource)]
+pub struct State {
+    mode: Mode,
+    repeat: [u32; 3],
+    matrix: [[i32; 3]; 3],
+    /// Apply to all frames in trajectory
+    all_frames: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Repeat,
+            repeat: [2, 2, 2],
+            matrix: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            all_frames: false,
+        }
+    }
+}
+
+impl State {
+    /// The transformation matrix for building supercell.
+    fn matrix(&self) -> [[i32; 3]; 3] {
+        match self.mode {
+            Mode::Repeat => {
+                let [n, m, k] = self.repeat.map(|x| x as i32);
+                [[n, 0, 0], [0, m, 0], [0, 0, k]]
+            }
+            Mode::Matrix => self.matrix,
+        }
+    }
+
+    /// Show supercell options. Return true if build button clicked.
+    fn show(&mut self, ui: &mut Ui, natoms: usize) -> bool {
+        ui.radio_value(&mut self.mode, Mode::Repeat, "Repeat along a, b, c");
+        ui.add_enabled_ui(self.mode == Mode::Repeat, |ui| {
+            ui.horizontal(|ui| {
+                for (i, x) in self.repeat.iter_mut().enumerate() {
+                    if i > 0 {
+                        ui.label("×");
+                    }
+                    ui.add(egui::DragValue::new(x).speed(0.1).clamp_range(1..=20));
+                }
+            });
+        });
+        ui.radio_value(&mut self.mode, Mode::Matrix, "Transformation matrix");
+        ui.add_enabled_ui(self.mode == Mode::Matrix, |ui| {
+            egui::Grid::new("supercell_matrix_grid").num_columns(4).show(ui, |ui| {
+                for (label, row) in ["a'", "b'", "c'"].iter().zip(self.matrix.iter_mut()) {
+                    ui.label(*label);
+                    for x in row.iter_mut() {
+                        ui.add(egui::DragValue::new(x).speed(0.1).clamp_range(-20..=20));
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui
+                .button("√3×√3 R30°")
+                .on_hover_text("For hexagonal lattice with γ = 120°")
+                .clicked()
+            {
+                self.matrix = [[2, 1, 0], [-1, 1, 0], [0, 0, 1]];
+            }
+        });
+        ui.separator();
+
+        let det = determinant(&self.matrix()).abs();
+        ui.label(format!("{det} cells, {} atoms", det as usize * natoms));
+        ui.checkbox(&mut self.all_frames, "Apply to all frames");
+        ui.add_enabled(det > 0, egui::Button::new("Build")).clicked()
+    }
+}
+// c95d1e38 ends here
+
+// [[file:../../bevy.note::4a1e96bf][4a1e96bf]]
+/// Build supercell for current frame or all frames, replacing the
+/// displayed structure.
+pub fn supercell_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    mut traj: ResMut<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut selected_atoms: ResMut<SelectedAtoms>,
+    mut history: ResMut<super::history::History>,
+) {
+    if !ui_state.supercell_window_open {
+        return;
+    }
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+
+    let ctx = contexts.ctx_mut();
+    let mut build = false;
+    let natoms = mol.natoms();
+    egui::Window::new("Build supercell")
+        .open(&mut ui_state.supercell_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            build = state.show(ui, natoms);
+        });
+    if !build {
+        return;
+    }
+
+    let matrix = state.matrix();
+    let description = "Build supercell";
+    let result = if state.all_frames {
+        traj.get_molecules()
+            .iter()
+            .map(|mol| build_supercell(mol, &matrix))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|mols| {
+                history.record_trajectory(description, &traj);
+                traj.replace_frames(mols);
+            })
+    } else {
+        build_supercell(mol, &matrix).map(|supercell| {
+            history.record_current_frame(description, &traj, &current_frame);
+            if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+                *mol = supercell;
+            }
+        })
+    };
+    ui_state.message = match result {
+        Ok(_) => format!(
+            "Supercell built with {} atoms",
+            traj.get_current_molecule(&current_frame).map_or(0, |m| m.natoms())
+        ),
+        Err(err) => err,
+    };
+    // atoms are renumbered in supercell
+    selected_atoms.0.clear();
+}
+// 4a1e96bf ends here
@@ -0,0 +1,241 @@
+// [[file:../../bevy.note::7d3b5e19][7d3b5e19]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_mod_picking::prelude::PickSelection;
+use egui::Ui;
+use gchemol::Molecule;
+
+use crate::base::{AtomIndex, CurrentFrame};
+use crate::molecule::MoleculeTrajectory;
+
+mod spacegroup;
+
+use spacegroup::{Operation, SpaceGroup};
+// 7d3b5e19 ends here
+
+// [[file:../../bevy.note::c2a84f60][c2a84f60]]
+/// Find space group of periodic structure in `mol`. Return the found
+/// symmetry and atom serial numbers in the order of atom indices
+/// used in it.
+fn find_symmetry(mol: &Molecule, symprec: f64) -> Result<(SpaceGroup, Vec<usize>), String> {
+    let lat = mol.get_lattice().ok_or("No lattice in current molecule")?;
+    let lattice = lat.vectors().map(|v| v.into());
+    let serials: Vec<usize> = mol.numbers().collect();
+    let positions: Vec<[f64; 3]> = mol.positions().collect();
+    let numbers: Vec<usize> = mol.atoms().map(|(_, a)| a.number()).collect();
+    let sg = spacegroup::find_space_group(lattice, &positions, &numbers, symprec)?;
+    Ok((sg, serials))
+}
+
+/// Format fractional translation `t` as coordinate triplet.
+fn format_translation(t: [f64; 3]) -> String {
+    let op = Operation {
+        rotation: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        translation: t,
+    };
+    format!("({})", op.to_xyz())
+}
+// c2a84f60 ends here
+
+// [[file:../../bevy.note::5f09e2ab][5f09e2ab]]
+#[derive(Debug, Resource)]
+pub struct State {
+    /// Distance tolerance in Å for matching atoms
+    symprec: f64,
+    /// Found symmetry with atom serial numbers
+    result: Option<Result<(SpaceGroup, Vec<usize>), String>>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            symprec: 0.01,
+            result: None,
+        }
+    }
+}
+
+/// Actions on atom selection
+enum Action {
+    /// Select the given atoms
+    Select(Vec<usize>),
+    /// Extend selection to all symmetry-equivalent atoms
+    SelectEquivalent,
+}
+
+impl State {
+    /// Return the atoms symmetry-equivalent to any of `atoms`, including
+    /// themselves.
+    fn equivalent_atoms(&self, atoms: &[usize]) -> Vec<usize> {
+        let Some(Ok((sg, serials))) = &self.result else {
+            return vec![];
+        };
+        sg.equivalent_atoms
+            .iter()
+            .map(|group| group.atoms.iter().map(|&i| serials[i]).collect::<Vec<_>>())
+            .filter(|group| group.iter().any(|i| atoms.contains(i)))
+            .flatten()
+            .collect()
+    }
+
+    fn show(&mut self, ui: &mut Ui, mol: &Molecule, nselected: usize) -> Option<Action> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.label("Tolerance");
+            ui.add(
+                egui::DragValue::new(&mut self.symprec)
+                    .speed(0.001)
+                    .clamp_range(0.0001..=1.0)
+                    .max_decimals(4)
+                    .suffix(" Å"),
+            )
+            .on_hover_text("Atoms within this distance are considered as overlapped");
+            if ui.button("Find symmetry").clicked() {
+                self.result = Some(find_symmetry(mol, self.symprec));
+            }
+        });
+        ui.separator();
+
+        let (sg, serials) = match &self.result {
+            None => {
+                ui.label("Find symmetry of current frame.");
+                return action;
+            }
+            Some(Err(err)) => {
+                ui.label(err.as_str());
+                return action;
+            }
+            Some(Ok(result)) => result,
+        };
+
+        egui::Grid::new("symmetry_summary_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Space group");
+            match sg.number {
+                Some(n) => ui.label(format!("{} (No. {n})", sg.symbol)),
+                None => ui.label(format!("{} (not identified)", sg.symbol)),
+            };
+            ui.end_row();
+            ui.label("Point group");
+            ui.label(sg.point_group);
+            ui.end_row();
+            ui.label("Crystal system");
+            ui.label(sg.crystal_system);
+            ui.end_row();
+            ui.label("Conventional cell");
+            let lat = gchemol::Lattice::new(sg.conventional_lattice);
+            let [a, b, c] = lat.lengths();
+            let [alpha, beta, gamma] = lat.angles();
+            ui.label(format!("{a:.4} {b:.4} {c:.4} Å, {alpha:.2} {beta:.2} {gamma:.2}°"));
+            ui.end_row();
+        });
+
+        let nops = sg.operations.len() * sg.centering.len();
+        egui::CollapsingHeader::new(format!("Symmetry operations ({nops})")).show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_source("symmetry_operations")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    if sg.centering.len() > 1 {
+                        let centering: Vec<_> = sg.centering.iter().map(|t| format_translation(*t)).collect();
+                        ui.label(format!("Centering: {}+", centering.join(" ")));
+                    }
+                    for (i, op) in sg.operations.iter().enumerate() {
+                        ui.monospace(format!("{:>3}  {}", i + 1, op.to_xyz()));
+                    }
+                });
+        });
+
+        egui::CollapsingHeader::new(format!("Equivalent atoms ({})", sg.equivalent_atoms.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("symmetry_equivalent_atoms")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("symmetry_equivalent_atoms_grid")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label("Element");
+                                ui.label("Atoms");
+                                ui.label("Multiplicity");
+                                ui.label("Site symmetry");
+                                ui.end_row();
+                                for group in &sg.equivalent_atoms {
+                                    let atoms: Vec<_> = group.atoms.iter().map(|&i| serials[i]).collect();
+                                    ui.label(mol.get_atom_unchecked(atoms[0]).symbol());
+                                    let text = gut::utils::abbreviate_numbers_human_readable(&atoms)
+                                        .unwrap_or_else(|_| format!("{atoms:?}"));
+                                    ui.label(text);
+                                    ui.label(group.multiplicity.to_string());
+                                    ui.label(group.site_symmetry);
+                                    if ui.button("Select").clicked() {
+                                        action = Some(Action::Select(atoms));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.label("Multiplicities are given for the conventional cell.");
+            });
+
+        ui.separator();
+        if ui
+            .add_enabled(nselected > 0, egui::Button::new("Select all symmetry-equivalent atoms"))
+            .on_hover_text("Extend selection to atoms equivalent to the selected ones")
+            .clicked()
+        {
+            action = Some(Action::SelectEquivalent);
+        }
+        action
+    }
+}
+// 5f09e2ab ends here
+
+// [[file:../../bevy.note::e8a1c7d3][e8a1c7d3]]
+/// Find crystal symmetry of current frame, and select
+/// symmetry-equivalent atoms.
+pub fn symmetry_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    traj: Res<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut selection_query: Query<(&AtomIndex, &mut PickSelection)>,
+) {
+    // the found symmetry is outdated, even if current molecule changed
+    // while the window is closed
+    if traj.is_changed() || current_frame.is_changed() {
+        state.result = None;
+    }
+    if !ui_state.symmetry_window_open {
+        return;
+    }
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+
+    let selected = crate::molecule::get_selected_atoms(&selection_query);
+    let ctx = contexts.ctx_mut();
+    let mut action = None;
+    egui::Window::new("Crystal symmetry")
+        .open(&mut ui_state.symmetry_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            action = state.show(ui, mol, selected.len());
+        });
+
+    let atoms = match action {
+        Some(Action::Select(atoms)) => atoms,
+        Some(Action::SelectEquivalent) => state.equivalent_atoms(&selected),
+        None => return,
+    };
+    for (AtomIndex(i), mut selection) in selection_query.iter_mut() {
+        let selected = atoms.contains(i);
+        if selection.is_selected != selected {
+            selection.is_selected = selected;
+        }
+    }
+    ui_state.message = format!("{} atoms selected", atoms.len());
+}
+// e8a1c7d3 ends here
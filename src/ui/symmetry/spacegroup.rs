@@ -0,0 +1,1333 @@
+// [[file:../../../bevy.note::1f6a9c30][1f6a9c30]]
+use bevy::math::{DMat3, DVec3};
+// 1f6a9c30 ends here
+
+// [[file:../../../bevy.note::8d2e4b71][8d2e4b71]]
+/// Integer rotation matrix acting on fractional coordinates, in rows
+pub type Rotation = [[i32; 3]; 3];
+
+const IDENTITY: Rotation = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+
+/// Tolerance for comparing fractional translations of symmetry
+/// operations, which are multiples of 1/12 or 1/8
+const FRAC_TOLERANCE: f64 = 0.04;
+
+fn to_dmat3(rot: &Rotation) -> DMat3 {
+    DMat3::from_cols_array_2d(&rot.map(|row| row.map(|x| x as f64))).transpose()
+}
+
+fn to_rotation(m: DMat3) -> Rotation {
+    m.transpose().to_cols_array_2d().map(|row| row.map(|x| x.round() as i32))
+}
+
+fn to_dvec3(v: [i32; 3]) -> DVec3 {
+    DVec3::new(v[0] as f64, v[1] as f64, v[2] as f64)
+}
+
+fn determinant(rot: &Rotation) -> i32 {
+    to_dmat3(rot).determinant().round() as i32
+}
+
+fn cross(u: [i32; 3], v: [i32; 3]) -> [i32; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Wrap fractional coordinates into [0, 1).
+fn wrap(v: DVec3) -> DVec3 {
+    let w = v - v.floor();
+    DVec3::select(w.cmpgt(DVec3::splat(1.0 - 1e-8)), w - 1.0, w)
+}
+
+/// Return true if all components of `v` are close to integers.
+fn is_integral(v: DVec3, tolerance: f64) -> bool {
+    (v - v.round()).abs().max_element() < tolerance
+}
+
+/// Rotation types in the order for identifying point groups: the
+/// negative ones are rotoinversions, and -2 is mirror.
+const ROTATION_TYPES: [i32; 10] = [-6, -4, -3, -2, -1, 1, 2, 3, 4, 6];
+
+fn rotation_type(rot: &Rotation) -> i32 {
+    let trace = rot[0][0] + rot[1][1] + rot[2][2];
+    match (determinant(rot), trace) {
+        (1, 3) => 1,
+        (1, -1) => 2,
+        (1, 0) => 3,
+        (1, 1) => 4,
+        (1, 2) => 6,
+        (-1, -3) => -1,
+        (-1, 1) => -2,
+        (-1, 0) => -3,
+        (-1, -1) => -4,
+        (-1, -2) => -6,
+        _ => 0,
+    }
+}
+
+/// Return the rotation axis of `rot` as primitive integer vector, or
+/// the normal for a mirror.
+fn rotation_axis(rot: &Rotation) -> Option<[i32; 3]> {
+    let det = determinant(rot);
+    let mut m = rot.map(|row| row.map(|x| x * det));
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] -= 1;
+    }
+    [(0, 1), (0, 2), (1, 2)].into_iter().find_map(|(i, j)| {
+        let u = cross(m[i], m[j]);
+        let g = gcd(gcd(u[0], u[1]), u[2]);
+        (g != 0).then(|| {
+            // make the first non-zero component positive
+            let sign = if u.iter().find(|&&x| x != 0).copied().unwrap_or(1) < 0 {
+                -g
+            } else {
+                g
+            };
+            u.map(|x| x / sign)
+        })
+    })
+}
+
+/// Point groups with crystal systems, identified by the numbers of
+/// rotations in `ROTATION_TYPES`.
+const POINT_GROUPS: [(&str, &str, [usize; 10]); 32] = [
+    ("1", "triclinic", [0, 0, 0, 0, 0, 1, 0, 0, 0, 0]),
+    ("-1", "triclinic", [0, 0, 0, 0, 1, 1, 0, 0, 0, 0]),
+    ("2", "monoclinic", [0, 0, 0, 0, 0, 1, 1, 0, 0, 0]),
+    ("m", "monoclinic", [0, 0, 0, 1, 0, 1, 0, 0, 0, 0]),
+    ("2/m", "monoclinic", [0, 0, 0, 1, 1, 1, 1, 0, 0, 0]),
+    ("222", "orthorhombic", [0, 0, 0, 0, 0, 1, 3, 0, 0, 0]),
+    ("mm2", "orthorhombic", [0, 0, 0, 2, 0, 1, 1, 0, 0, 0]),
+    ("mmm", "orthorhombic", [0, 0, 0, 3, 1, 1, 3, 0, 0, 0]),
+    ("4", "tetragonal", [0, 0, 0, 0, 0, 1, 1, 0, 2, 0]),
+    ("-4", "tetragonal", [0, 2, 0, 0, 0, 1, 1, 0, 0, 0]),
+    ("4/m", "tetragonal", [0, 2, 0, 1, 1, 1, 1, 0, 2, 0]),
+    ("422", "tetragonal", [0, 0, 0, 0, 0, 1, 5, 0, 2, 0]),
+    ("4mm", "tetragonal", [0, 0, 0, 4, 0, 1, 1, 0, 2, 0]),
+    ("-42m", "tetragonal", [0, 2, 0, 2, 0, 1, 3, 0, 0, 0]),
+    ("4/mmm", "tetragonal", [0, 2, 0, 5, 1, 1, 5, 0, 2, 0]),
+    ("3", "trigonal", [0, 0, 0, 0, 0, 1, 0, 2, 0, 0]),
+    ("-3", "trigonal", [0, 0, 2, 0, 1, 1, 0, 2, 0, 0]),
+    ("32", "trigonal", [0, 0, 0, 0, 0, 1, 3, 2, 0, 0]),
+    ("3m", "trigonal", [0, 0, 0, 3, 0, 1, 0, 2, 0, 0]),
+    ("-3m", "trigonal", [0, 0, 2, 3, 1, 1, 3, 2, 0, 0]),
+    ("6", "hexagonal", [0, 0, 0, 0, 0, 1, 1, 2, 0, 2]),
+    ("-6", "hexagonal", [2, 0, 0, 1, 0, 1, 0, 2, 0, 0]),
+    ("6/m", "hexagonal", [2, 0, 2, 1, 1, 1, 1, 2, 0, 2]),
+    ("622", "hexagonal", [0, 0, 0, 0, 0, 1, 7, 2, 0, 2]),
+    ("6mm", "hexagonal", [0, 0, 0, 6, 0, 1, 1, 2, 0, 2]),
+    ("-6m2", "hexagonal", [2, 0, 0, 4, 0, 1, 3, 2, 0, 0]),
+    ("6/mmm", "hexagonal", [2, 0, 2, 7, 1, 1, 7, 2, 0, 2]),
+    ("23", "cubic", [0, 0, 0, 0, 0, 1, 3, 8, 0, 0]),
+    ("m-3", "cubic", [0, 0, 8, 3, 1, 1, 3, 8, 0, 0]),
+    ("432", "cubic", [0, 0, 0, 0, 0, 1, 9, 8, 6, 0]),
+    ("-43m", "cubic", [0, 6, 0, 6, 0, 1, 3, 8, 0, 0]),
+    ("m-3m", "cubic", [0, 6, 8, 9, 1, 1, 9, 8, 6, 0]),
+];
+
+/// Return the point group symbol and crystal system of `rotations`.
+fn point_group<'a>(rotations: impl IntoIterator<Item = &'a Rotation>) -> Option<(&'static str, &'static str)> {
+    let mut counts = [0; 10];
+    for rot in rotations {
+        let k = ROTATION_TYPES.iter().position(|&t| t == rotation_type(rot))?;
+        counts[k] += 1;
+    }
+    POINT_GROUPS
+        .iter()
+        .find(|(.., c)| *c == counts)
+        .map(|&(symbol, system, _)| (symbol, system))
+}
+
+/// Short Hermann-Mauguin symbols of the 230 space groups in standard
+/// settings, in the order of space group numbers
+const SPACE_GROUPS: [&str; 230] = [
+    "P1",
+    "P-1",
+    "P2",
+    "P2_1",
+    "C2",
+    "Pm",
+    "Pc",
+    "Cm",
+    "Cc",
+    "P2/m",
+    "P2_1/m",
+    "C2/m",
+    "P2/c",
+    "P2_1/c",
+    "C2/c",
+    "P222",
+    "P222_1",
+    "P2_12_12",
+    "P2_12_12_1",
+    "C222_1",
+    "C222",
+    "F222",
+    "I222",
+    "I2_12_12_1",
+    "Pmm2",
+    "Pmc2_1",
+    "Pcc2",
+    "Pma2",
+    "Pca2_1",
+    "Pnc2",
+    "Pmn2_1",
+    "Pba2",
+    "Pna2_1",
+    "Pnn2",
+    "Cmm2",
+    "Cmc2_1",
+    "Ccc2",
+    "Amm2",
+    "Aem2",
+    "Ama2",
+    "Aea2",
+    "Fmm2",
+    "Fdd2",
+    "Imm2",
+    "Iba2",
+    "Ima2",
+    "Pmmm",
+    "Pnnn",
+    "Pccm",
+    "Pban",
+    "Pmma",
+    "Pnna",
+    "Pmna",
+    "Pcca",
+    "Pbam",
+    "Pccn",
+    "Pbcm",
+    "Pnnm",
+    "Pmmn",
+    "Pbcn",
+    "Pbca",
+    "Pnma",
+    "Cmcm",
+    "Cmce",
+    "Cmmm",
+    "Cccm",
+    "Cmme",
+    "Ccce",
+    "Fmmm",
+    "Fddd",
+    "Immm",
+    "Ibam",
+    "Ibca",
+    "Imma",
+    "P4",
+    "P4_1",
+    "P4_2",
+    "P4_3",
+    "I4",
+    "I4_1",
+    "P-4",
+    "I-4",
+    "P4/m",
+    "P4_2/m",
+    "P4/n",
+    "P4_2/n",
+    "I4/m",
+    "I4_1/a",
+    "P422",
+    "P42_12",
+    "P4_122",
+    "P4_12_12",
+    "P4_222",
+    "P4_22_12",
+    "P4_322",
+    "P4_32_12",
+    "I422",
+    "I4_122",
+    "P4mm",
+    "P4bm",
+    "P4_2cm",
+    "P4_2nm",
+    "P4cc",
+    "P4nc",
+    "P4_2mc",
+    "P4_2bc",
+    "I4mm",
+    "I4cm",
+    "I4_1md",
+    "I4_1cd",
+    "P-42m",
+    "P-42c",
+    "P-42_1m",
+    "P-42_1c",
+    "P-4m2",
+    "P-4c2",
+    "P-4b2",
+    "P-4n2",
+    "I-4m2",
+    "I-4c2",
+    "I-42m",
+    "I-42d",
+    "P4/mmm",
+    "P4/mcc",
+    "P4/nbm",
+    "P4/nnc",
+    "P4/mbm",
+    "P4/mnc",
+    "P4/nmm",
+    "P4/ncc",
+    "P4_2/mmc",
+    "P4_2/mcm",
+    "P4_2/nbc",
+    "P4_2/nnm",
+    "P4_2/mbc",
+    "P4_2/mnm",
+    "P4_2/nmc",
+    "P4_2/ncm",
+    "I4/mmm",
+    "I4/mcm",
+    "I4_1/amd",
+    "I4_1/acd",
+    "P3",
+    "P3_1",
+    "P3_2",
+    "R3",
+    "P-3",
+    "R-3",
+    "P312",
+    "P321",
+    "P3_112",
+    "P3_121",
+    "P3_212",
+    "P3_221",
+    "R32",
+    "P3m1",
+    "P31m",
+    "P3c1",
+    "P31c",
+    "R3m",
+    "R3c",
+    "P-31m",
+    "P-31c",
+    "P-3m1",
+    "P-3c1",
+    "R-3m",
+    "R-3c",
+    "P6",
+    "P6_1",
+    "P6_5",
+    "P6_2",
+    "P6_4",
+    "P6_3",
+    "P-6",
+    "P6/m",
+    "P6_3/m",
+    "P622",
+    "P6_122",
+    "P6_522",
+    "P6_222",
+    "P6_422",
+    "P6_322",
+    "P6mm",
+    "P6cc",
+    "P6_3cm",
+    "P6_3mc",
+    "P-6m2",
+    "P-6c2",
+    "P-62m",
+    "P-62c",
+    "P6/mmm",
+    "P6/mcc",
+    "P6_3/mcm",
+    "P6_3/mmc",
+    "P23",
+    "F23",
+    "I23",
+    "P2_13",
+    "I2_13",
+    "Pm-3",
+    "Pn-3",
+    "Fm-3",
+    "Fd-3",
+    "Im-3",
+    "Pa-3",
+    "Ia-3",
+    "P432",
+    "P4_232",
+    "F432",
+    "F4_132",
+    "I432",
+    "P4_332",
+    "P4_132",
+    "I4_132",
+    "P-43m",
+    "F-43m",
+    "I-43m",
+    "P-43n",
+    "F-43c",
+    "I-43d",
+    "Pm-3m",
+    "Pn-3n",
+    "Pm-3n",
+    "Pn-3m",
+    "Fm-3m",
+    "Fm-3c",
+    "Fd-3m",
+    "Fd-3c",
+    "Im-3m",
+    "Ia-3d",
+];
+// 8d2e4b71 ends here
+
+// [[file:../../../bevy.note::5c7a0e92][5c7a0e92]]
+/// Periodic structure in fractional coordinates
+struct Structure<'a> {
+    /// Lattice vectors in columns
+    lattice: DMat3,
+    frac: Vec<DVec3>,
+    numbers: &'a [usize],
+    symprec: f64,
+}
+
+impl<'a> Structure<'a> {
+    fn new(lattice: DMat3, positions: &[DVec3], numbers: &'a [usize], symprec: f64) -> Self {
+        let inv = lattice.inverse();
+        let frac = positions.iter().map(|p| wrap(inv * *p)).collect();
+        Self {
+            lattice,
+            frac,
+            numbers,
+            symprec,
+        }
+    }
+
+    /// Return true if atoms at fractional coordinates `fi` and `fj` overlap.
+    fn overlap(&self, fi: DVec3, fj: DVec3) -> bool {
+        let d = fi - fj;
+        (self.lattice * (d - d.round())).length() < self.symprec
+    }
+
+    /// Return the atoms mapped from each atom if `rot` and `trans`
+    /// define a symmetry operation.
+    fn mapping(&self, rot: &Rotation, trans: DVec3) -> Option<Vec<usize>> {
+        let rot = to_dmat3(rot);
+        self.frac
+            .iter()
+            .zip(self.numbers)
+            .map(|(&f, n)| {
+                let p = rot * f + trans;
+                (0..self.frac.len()).find(|&k| self.numbers[k] == *n && self.overlap(p, self.frac[k]))
+            })
+            .collect()
+    }
+
+    /// Find the translation for rotation `rot` to be a symmetry
+    /// operation. Return the translations with atom mappings.
+    fn find_translations(&self, rot: &Rotation, first_only: bool) -> Vec<(DVec3, Vec<usize>)> {
+        // candidates from atoms of the least abundant element
+        let count = |n: usize| self.numbers.iter().filter(|&&x| x == n).count();
+        let Some(i0) = (0..self.numbers.len()).min_by_key(|&i| count(self.numbers[i])) else {
+            return vec![];
+        };
+        let rf = to_dmat3(rot) * self.frac[i0];
+        let mut found = vec![];
+        for k in (0..self.numbers.len()).filter(|&k| self.numbers[k] == self.numbers[i0]) {
+            let t = wrap(self.frac[k] - rf);
+            if let Some(mapping) = self.mapping(rot, t) {
+                found.push((t, mapping));
+                if first_only {
+                    break;
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Return a primitive lattice in columns from `lattice` with extra
+/// lattice `translations` in fractional coordinates. Short vectors are
+/// preferred.
+fn primitive_lattice(lattice: DMat3, translations: &[DVec3]) -> Result<DMat3, String> {
+    let volume = lattice.determinant().abs() / translations.len() as f64;
+    let mut candidates = vec![];
+    for t in translations {
+        for n in 0..27 {
+            let shift = DVec3::new((n % 3) as f64, (n / 3 % 3) as f64, (n / 9) as f64) - 1.0;
+            let v = lattice * (*t + shift);
+            if v.length() > 1e-6 {
+                candidates.push(v);
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.length().total_cmp(&b.length()));
+    candidates.truncate(60);
+
+    let n = candidates.len();
+    for i in 0..n {
+        for j in i + 1..n {
+            for k in j + 1..n {
+                let m = DMat3::from_cols(candidates[i], candidates[j], candidates[k]);
+                let det = m.determinant();
+                if (det.abs() / volume - 1.0).abs() < 0.1 {
+                    return Ok(if det < 0.0 {
+                        DMat3::from_cols(m.x_axis, m.y_axis, -m.z_axis)
+                    } else {
+                        m
+                    });
+                }
+            }
+        }
+    }
+    Err("Failed to find primitive cell".into())
+}
+
+/// Return lattice points of `lattice` sorted by length, excluding the
+/// origin.
+fn lattice_points(lattice: DMat3, range: i32) -> Vec<([i32; 3], DVec3)> {
+    let mut points = vec![];
+    for i in -range..=range {
+        for j in -range..=range {
+            for k in -range..=range {
+                if [i, j, k] != [0; 3] {
+                    points.push(([i, j, k], lattice * to_dvec3([i, j, k])));
+                }
+            }
+        }
+    }
+    points.sort_by(|a, b| a.1.length().total_cmp(&b.1.length()));
+    points
+}
+
+/// Return the rotations keeping `lattice` invariant, in fractional
+/// coordinates.
+fn lattice_rotations(lattice: DMat3, symprec: f64) -> Vec<Rotation> {
+    let points = lattice_points(lattice, 2);
+    let basis = [lattice.x_axis, lattice.y_axis, lattice.z_axis];
+    // candidate images of basis vectors with the same lengths
+    let images: Vec<Vec<_>> = basis
+        .iter()
+        .map(|b| {
+            points
+                .iter()
+                .filter(|(_, v)| (v.length() - b.length()).abs() < symprec)
+                .collect()
+        })
+        .collect();
+    let same_angle = |u: DVec3, v: DVec3, a: DVec3, b: DVec3| (u.dot(v) - a.dot(b)).abs() < symprec * (a.length() + b.length());
+
+    let mut rotations = vec![];
+    for (n1, v1) in &images[0] {
+        for (n2, v2) in images[1].iter().filter(|(_, v2)| same_angle(*v1, *v2, basis[0], basis[1])) {
+            for (n3, _) in images[2]
+                .iter()
+                .filter(|(_, v3)| same_angle(*v1, *v3, basis[0], basis[2]) && same_angle(*v2, *v3, basis[1], basis[2]))
+            {
+                // images of basis vectors in columns
+                let rot = [0, 1, 2].map(|i| [n1[i], n2[i], n3[i]]);
+                if determinant(&rot).abs() == 1 {
+                    rotations.push(rot);
+                }
+            }
+        }
+    }
+    rotations
+}
+// 5c7a0e92 ends here
+
+// [[file:../../../bevy.note::a3f69d04][a3f69d04]]
+/// Symmetry operations in a conventional cell for deriving space group
+/// symbol
+#[derive(Debug, Clone)]
+struct Setting {
+    /// Conventional cell vectors in columns, in Cartesian coordinates
+    basis: DMat3,
+    /// Rotations and translations in conventional cell
+    operations: Vec<(Rotation, DVec3)>,
+    /// Centering translations including zero
+    centering: Vec<DVec3>,
+    /// Centering letter: P, A, B, C, I, F or R
+    letter: char,
+}
+
+/// Return the centering letter of `centering` translations. The
+/// reverse setting of rhombohedral lattice is marked as 'r'.
+fn centering_letter(centering: &[DVec3]) -> Option<char> {
+    let has = |v: [f64; 3]| centering.iter().any(|c| is_integral(*c - DVec3::from(v), FRAC_TOLERANCE));
+    let letter = match centering.len() {
+        1 => 'P',
+        2 if has([0.0, 0.5, 0.5]) => 'A',
+        2 if has([0.5, 0.0, 0.5]) => 'B',
+        2 if has([0.5, 0.5, 0.0]) => 'C',
+        2 if has([0.5, 0.5, 0.5]) => 'I',
+        3 if has([2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0]) => 'R',
+        3 if has([1.0 / 3.0, 2.0 / 3.0, 1.0 / 3.0]) => 'r',
+        4 if has([0.0, 0.5, 0.5]) && has([0.5, 0.0, 0.5]) => 'F',
+        _ => return None,
+    };
+    Some(letter)
+}
+
+impl Setting {
+    /// Transform primitive `lattice` and `operations` into conventional
+    /// cell defined by `transform`.
+    fn new(lattice: DMat3, operations: &[(Rotation, DVec3)], transform: Rotation) -> Result<Self, String> {
+        let m = to_dmat3(&transform);
+        if m.determinant() < 0.5 {
+            return Err("Invalid conventional cell".into());
+        }
+        // fractional coordinates: x(primitive) = Mᵀ x(conventional)
+        let mt_inv = m.transpose().inverse();
+        let mut centering = vec![DVec3::ZERO];
+        let mut k = 0;
+        while k < centering.len() && centering.len() <= 4 {
+            for i in 0..3 {
+                let c = wrap(centering[k] + mt_inv.col(i));
+                if !centering.iter().any(|x| is_integral(*x - c, FRAC_TOLERANCE)) {
+                    centering.push(c);
+                }
+            }
+            k += 1;
+        }
+        let letter = centering_letter(&centering).ok_or("Unknown lattice centering")?;
+        if letter == 'r' {
+            // switch to obverse setting by rotating 180° about c
+            let [a, b, c] = transform;
+            return Self::new(lattice, operations, [a.map(|x| -x), b.map(|x| -x), c]);
+        }
+
+        let operations = operations
+            .iter()
+            .map(|(r, t)| (to_rotation(mt_inv * to_dmat3(r) * m.transpose()), wrap(mt_inv * *t)))
+            .collect();
+        Ok(Self {
+            basis: lattice * m.transpose(),
+            operations,
+            centering,
+            letter,
+        })
+    }
+
+    /// Return the operations with axes or mirror normals along `d`.
+    fn operations_along(&self, d: [i32; 3]) -> impl Iterator<Item = &(Rotation, DVec3)> {
+        self.operations
+            .iter()
+            .filter(move |(r, _)| rotation_axis(r).is_some_and(|u| cross(u, d) == [0; 3]))
+    }
+
+    /// Return all translations equivalent to `t` within a cell.
+    fn variants(&self, t: DVec3) -> impl Iterator<Item = DVec3> + '_ {
+        self.centering
+            .iter()
+            .flat_map(move |c| (0..8).map(move |n| t + *c + DVec3::new((n & 1) as f64, (n >> 1 & 1) as f64, (n >> 2) as f64)))
+    }
+
+    fn is_lattice_vector(&self, v: DVec3) -> bool {
+        self.centering.iter().any(|c| is_integral(v - *c, FRAC_TOLERANCE))
+    }
+
+    /// Return true if `rot` rotates counterclockwise about `axis` in
+    /// Cartesian coordinates.
+    fn is_positive_rotation(&self, rot: &Rotation, axis: DVec3) -> bool {
+        let r = self.basis * to_dmat3(rot) * self.basis.inverse();
+        let v = axis.any_orthonormal_vector();
+        v.cross(r * v).dot(axis) > 0.0
+    }
+
+    /// Return the symbols of rotation or screw axes along `d`, in the
+    /// order of preference.
+    fn axis_symbols(&self, d: [i32; 3]) -> Vec<String> {
+        let proper = |r: &Rotation| determinant(r) == 1;
+        let order = self
+            .operations_along(d)
+            .filter(|(r, _)| proper(r))
+            .map(|(r, _)| rotation_type(r))
+            .max()
+            .unwrap_or(1);
+        if order < 2 {
+            return vec![];
+        }
+        let dv = to_dvec3(d);
+        // the shortest lattice translation along the axis: d/k
+        let k = (1..=4).rev().find(|&k| self.is_lattice_vector(dv / k as f64)).unwrap_or(1);
+        let axis = self.basis * dv;
+        let mut screws = vec![];
+        for (r, t) in self
+            .operations_along(d)
+            .filter(|(r, _)| proper(r) && rotation_type(r) == order)
+        {
+            if order > 2 && !self.is_positive_rotation(r, axis) {
+                continue;
+            }
+            let rf = to_dmat3(r);
+            for tau in self.variants(*t) {
+                // intrinsic translation along the axis
+                let mut x = tau;
+                let mut sum = DVec3::ZERO;
+                for _ in 0..order {
+                    sum += x;
+                    x = rf * x;
+                }
+                let f = sum.dot(dv) / dv.length_squared() / order as f64 * k as f64;
+                screws.push(((f * order as f64).round() as i32).rem_euclid(order));
+            }
+        }
+        screws.sort();
+        screws.dedup();
+        screws
+            .into_iter()
+            .map(|s| if s == 0 { format!("{order}") } else { format!("{order}_{s}") })
+            .collect()
+    }
+
+    /// Return the symbols of mirror or glide planes normal to `d`, in
+    /// the order of preference.
+    fn plane_symbols(&self, d: [i32; 3]) -> Vec<String> {
+        let mut letters = vec![];
+        for (r, t) in self.operations_along(d).filter(|(r, _)| rotation_type(r) == -2) {
+            let rf = to_dmat3(r);
+            for tau in self.variants(*t) {
+                letters.push(glide_letter((tau + rf * tau) / 2.0));
+            }
+        }
+        let mut symbols: Vec<String> = "mabcnd".chars().filter(|x| letters.contains(x)).map(String::from).collect();
+        // double glide plane
+        if letters.iter().filter(|x| "abc".contains(**x)).count() > 1 {
+            symbols.push("e".into());
+        }
+        symbols
+    }
+
+    /// Return true if the 2-fold axes along a and b intersect, which
+    /// distinguishes I222 from I2₁2₁2₁, and I23 from I2₁3.
+    fn has_intersecting_axes(&self) -> bool {
+        let positions = |d: [i32; 3], i: usize| -> Vec<f64> {
+            self.operations_along(d)
+                .filter(|(r, _)| rotation_type(r) == 2)
+                .flat_map(|(_, t)| self.variants(*t).collect::<Vec<_>>())
+                .filter(|tau| (tau[i] - tau[i].round()).abs() < FRAC_TOLERANCE)
+                .map(|tau| tau.z / 2.0)
+                .collect()
+        };
+        let za = positions([1, 0, 0], 0);
+        let zb = positions([0, 1, 0], 1);
+        za.iter()
+            .any(|x| zb.iter().any(|y| ((x - y) - (x - y).round()).abs() < FRAC_TOLERANCE))
+    }
+}
+
+/// Return the letter of glide plane with glide vector `g`.
+fn glide_letter(g: DVec3) -> char {
+    let q = g.to_array().map(|x| (x * 4.0).round().rem_euclid(4.0) as i32);
+    if q.iter().any(|x| x % 2 == 1) {
+        'd'
+    } else {
+        match q.iter().filter(|&&x| x == 2).count() {
+            0 => 'm',
+            1 => ['a', 'b', 'c'][q.iter().position(|&x| x == 2).unwrap()],
+            _ => 'n',
+        }
+    }
+}
+// a3f69d04 ends here
+
+// [[file:../../../bevy.note::e60b5f2d][e60b5f2d]]
+const DIR_A: [i32; 3] = [1, 0, 0];
+const DIR_B: [i32; 3] = [0, 1, 0];
+const DIR_C: [i32; 3] = [0, 0, 1];
+const DIR_AB: [i32; 3] = [1, -1, 0];
+
+/// Return the candidate Hermann-Mauguin symbols of `setting` with point
+/// group `pg`, in the order of preference.
+fn symbol_candidates(setting: &Setting, pg: &str) -> Vec<String> {
+    let axis = |d| setting.axis_symbols(d);
+    let plane = |d| setting.plane_symbols(d);
+    let fixed = |s: &str| vec![s.to_string()];
+    let axis_plane = |d| -> Vec<String> {
+        let planes = plane(d);
+        axis(d)
+            .iter()
+            .flat_map(|a| planes.iter().map(move |p| format!("{a}/{p}")))
+            .collect()
+    };
+    // planes normal to a, b, or c in cubic system are all denoted as a
+    let cubic_plane = || -> Vec<String> {
+        let mut symbols: Vec<String> = [DIR_A, DIR_B, DIR_C]
+            .into_iter()
+            .flat_map(plane)
+            .filter(|p| p != "e")
+            .map(|p| if p == "b" || p == "c" { "a".into() } else { p })
+            .collect();
+        symbols.sort_by_key(|p| "mabcnd".find(p.as_str()));
+        symbols.dedup();
+        symbols
+    };
+    let rhombohedral = setting.letter == 'R';
+    let any_plane = || -> Vec<String> {
+        let mut symbols = plane(DIR_A);
+        symbols.extend(plane(DIR_AB));
+        symbols.sort_by_key(|p| "mabcnde".find(p.as_str()));
+        symbols.dedup();
+        symbols
+    };
+
+    let positions = match pg {
+        "1" | "-1" | "-4" | "-3" | "-6" => vec![fixed(pg)],
+        "2" => vec![axis(DIR_B)],
+        "m" => vec![plane(DIR_B)],
+        "2/m" => vec![axis_plane(DIR_B)],
+        "222" => vec![axis(DIR_A), axis(DIR_B), axis(DIR_C)],
+        "mm2" => vec![plane(DIR_A), plane(DIR_B), axis(DIR_C)],
+        "mmm" => vec![plane(DIR_A), plane(DIR_B), plane(DIR_C)],
+        "4" | "3" | "6" => vec![axis(DIR_C)],
+        "4/m" | "6/m" => vec![axis_plane(DIR_C)],
+        "422" | "622" => vec![axis(DIR_C), axis(DIR_A), axis(DIR_AB)],
+        "4mm" | "6mm" => vec![axis(DIR_C), plane(DIR_A), plane(DIR_AB)],
+        "4/mmm" | "6/mmm" => vec![axis_plane(DIR_C), plane(DIR_A), plane(DIR_AB)],
+        "-42m" if axis(DIR_A).is_empty() => vec![fixed("-4"), plane(DIR_A), axis(DIR_AB)],
+        "-42m" => vec![fixed("-4"), axis(DIR_A), plane(DIR_AB)],
+        "-6m2" if plane(DIR_A).is_empty() => vec![fixed("-6"), axis(DIR_A), plane(DIR_AB)],
+        "-6m2" => vec![fixed("-6"), plane(DIR_A), axis(DIR_AB)],
+        "32" if rhombohedral => vec![axis(DIR_C), fixed("2")],
+        "32" if axis(DIR_A).is_empty() => vec![axis(DIR_C), fixed("1"), axis(DIR_AB)],
+        "32" => vec![axis(DIR_C), axis(DIR_A), fixed("1")],
+        "3m" if rhombohedral => vec![axis(DIR_C), any_plane()],
+        "3m" if plane(DIR_A).is_empty() => vec![axis(DIR_C), fixed("1"), plane(DIR_AB)],
+        "3m" => vec![axis(DIR_C), plane(DIR_A), fixed("1")],
+        "-3m" if rhombohedral => vec![fixed("-3"), any_plane()],
+        "-3m" if plane(DIR_A).is_empty() => vec![fixed("-3"), fixed("1"), plane(DIR_AB)],
+        "-3m" => vec![fixed("-3"), plane(DIR_A), fixed("1")],
+        "23" => vec![axis(DIR_C), fixed("3")],
+        "m-3" => vec![cubic_plane(), fixed("-3")],
+        "432" => vec![axis(DIR_C), fixed("3"), fixed("2")],
+        "-43m" => vec![fixed("-4"), fixed("3"), plane(DIR_AB)],
+        "m-3m" => vec![cubic_plane(), fixed("-3"), plane(DIR_AB)],
+        _ => vec![],
+    };
+
+    let mut candidates = vec![setting.letter.to_string()];
+    for options in positions {
+        candidates = candidates
+            .iter()
+            .flat_map(|s| options.iter().map(move |x| format!("{s}{x}")))
+            .collect();
+    }
+    candidates
+}
+
+/// Return the transformation to conventional cell for `system`, in rows
+/// of primitive lattice coordinates. Cell vectors are chosen along
+/// symmetry axes of `rotations`.
+fn conventional_transform(lattice: DMat3, rotations: &[Rotation], system: &str, symprec: f64) -> Result<Rotation, String> {
+    let axes_of = |types: &[i32]| -> Vec<[i32; 3]> {
+        let mut axes = vec![];
+        for r in rotations.iter().filter(|r| types.contains(&rotation_type(r))) {
+            if let Some(u) = rotation_axis(r) {
+                if !axes.contains(&u) {
+                    axes.push(u);
+                }
+            }
+        }
+        axes
+    };
+    let rotation_of = |t: i32| rotations.iter().find(|r| rotation_type(r) == t).copied();
+    let apply = |r: &Rotation, v: [i32; 3]| r.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2]);
+    let points = lattice_points(lattice, 3);
+    // the lattice vectors normal to `axis`, shortest first
+    let in_plane = |axis: [i32; 3]| -> Vec<[i32; 3]> {
+        let normal = (lattice * to_dvec3(axis)).normalize();
+        points
+            .iter()
+            .filter(|(_, v)| v.dot(normal).abs() < symprec)
+            .map(|(n, _)| *n)
+            .collect()
+    };
+    let det = |m: &Rotation| determinant(m);
+    let error = || format!("Failed to find symmetry axes of {system} lattice");
+
+    let mut m = match system {
+        "triclinic" => IDENTITY,
+        "monoclinic" => {
+            let b = *axes_of(&[2, -2]).first().ok_or_else(error)?;
+            let vectors = in_plane(b);
+            let a = *vectors.first().ok_or_else(error)?;
+            let c = *vectors.iter().find(|&&c| cross(a, c) != [0; 3]).ok_or_else(error)?;
+            // obtuse β angle
+            let a = if (lattice * to_dvec3(a)).dot(lattice * to_dvec3(c)) > 0.0 {
+                a.map(|x| -x)
+            } else {
+                a
+            };
+            [a, b, c]
+        }
+        "orthorhombic" => {
+            let axes = axes_of(&[2, -2]);
+            if axes.len() != 3 {
+                return Err(error());
+            }
+            [axes[0], axes[1], axes[2]]
+        }
+        "tetragonal" => {
+            let c = *axes_of(&[4, -4]).first().ok_or_else(error)?;
+            let a = *in_plane(c).first().ok_or_else(error)?;
+            let r4 = rotation_of(4).or_else(|| rotation_of(-4).map(|r| r.map(|row| row.map(|x| -x))));
+            let b = apply(&r4.ok_or_else(error)?, a);
+            [a, b, c]
+        }
+        "trigonal" | "hexagonal" => {
+            let c = *axes_of(&[3, -3, 6, -6]).first().ok_or_else(error)?;
+            let a = *in_plane(c).first().ok_or_else(error)?;
+            let b = apply(&rotation_of(3).ok_or_else(error)?, a);
+            [a, b, c]
+        }
+        "cubic" => {
+            let mut axes = axes_of(&[4, -4]);
+            if axes.is_empty() {
+                axes = axes_of(&[2, -2]);
+            }
+            if axes.len() != 3 {
+                return Err(error());
+            }
+            [axes[0], axes[1], axes[2]]
+        }
+        _ => return Err(error()),
+    };
+    if det(&m) < 0 {
+        m[2] = m[2].map(|x| -x);
+    }
+    Ok(m)
+}
+
+/// Identify space group from `operations` in primitive `lattice`.
+/// Return the conventional setting with symbol and number.
+fn identify(
+    lattice: DMat3,
+    operations: &[(Rotation, DVec3)],
+    pg: &str,
+    system: &str,
+    symprec: f64,
+) -> Result<(Setting, String, Option<usize>), String> {
+    let rotations: Vec<_> = operations.iter().map(|(r, _)| *r).collect();
+    let transform = conventional_transform(lattice, &rotations, system, symprec)?;
+    let [a, b, c] = transform;
+    let neg = |v: [i32; 3]| v.map(|x| -x);
+    let transforms: Vec<Rotation> = match system {
+        // try all axis permutations for orthorhombic system
+        "orthorhombic" => vec![
+            [a, b, c],
+            [b, c, a],
+            [c, a, b],
+            [b, a, neg(c)],
+            [a, c, neg(b)],
+            [c, b, neg(a)],
+        ],
+        // try the three cell choices with unique axis b, and a
+        // interchanged with c, for the standard symbol
+        "monoclinic" => {
+            let ac = [0, 1, 2].map(|i| -a[i] - c[i]);
+            vec![
+                [a, b, c],
+                [c, b, ac],
+                [ac, b, a],
+                [c, neg(b), a],
+                [ac, neg(b), c],
+                [a, neg(b), ac],
+            ]
+        }
+        _ => vec![transform],
+    };
+
+    let mut fallback = None;
+    for transform in transforms {
+        let setting = Setting::new(lattice, operations, transform)?;
+        let candidates = symbol_candidates(&setting, pg);
+        for symbol in &candidates {
+            let symbol = match symbol.as_str() {
+                "I222" if !setting.has_intersecting_axes() => "I2_12_12_1",
+                "I23" if !setting.has_intersecting_axes() => "I2_13",
+                s => s,
+            };
+            if let Some(i) = SPACE_GROUPS.iter().position(|&s| s == symbol) {
+                return Ok((setting, symbol.to_string(), Some(i + 1)));
+            }
+        }
+        if fallback.is_none() {
+            let symbol = candidates.first().cloned().unwrap_or_else(|| "?".into());
+            fallback = Some((setting, symbol));
+        }
+    }
+    let (setting, symbol) = fallback.ok_or("No conventional cell found")?;
+    Ok((setting, symbol, None))
+}
+// e60b5f2d ends here
+
+// [[file:../../../bevy.note::4b8f1c6e][4b8f1c6e]]
+/// A symmetry operation in fractional coordinates: x' = R x + t
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub rotation: Rotation,
+    pub translation: [f64; 3],
+}
+
+/// Atoms equivalent by symmetry, occupying the same Wyckoff position
+#[derive(Debug, Clone)]
+pub struct EquivalentAtoms {
+    /// Indices of the atoms in input structure
+    pub atoms: Vec<usize>,
+    /// The multiplicity of Wyckoff position in conventional cell
+    pub multiplicity: usize,
+    /// The point group of site symmetry
+    pub site_symmetry: &'static str,
+}
+
+/// Symmetry found in a periodic structure
+#[derive(Debug, Clone)]
+pub struct SpaceGroup {
+    /// Space group number, or None if it was not identified
+    pub number: Option<usize>,
+    /// Short Hermann-Mauguin symbol
+    pub symbol: String,
+    pub point_group: &'static str,
+    pub crystal_system: &'static str,
+    /// Symmetry operations in conventional cell, without centering
+    pub operations: Vec<Operation>,
+    /// Centering translations of conventional cell, including zero
+    pub centering: Vec<[f64; 3]>,
+    /// Conventional cell vectors in rows
+    pub conventional_lattice: [[f64; 3]; 3],
+    pub equivalent_atoms: Vec<EquivalentAtoms>,
+}
+
+impl Operation {
+    /// Format as coordinate triplet, e.g. "-y,x-y,z+1/3".
+    pub fn to_xyz(&self) -> String {
+        let fraction = |x: f64| -> String {
+            for d in [2, 3, 4, 6, 8, 12] {
+                let n = x * d as f64;
+                if (n - n.round()).abs() < FRAC_TOLERANCE * d as f64 {
+                    let n = n.round() as i32;
+                    let g = gcd(n, d);
+                    return format!("+{}/{}", n / g, d / g);
+                }
+            }
+            format!("+{x:.4}")
+        };
+        (0..3)
+            .map(|i| {
+                let mut s = String::new();
+                for (j, x) in ["x", "y", "z"].iter().enumerate() {
+                    match self.rotation[i][j] {
+                        0 => (),
+                        1 if s.is_empty() => s.push_str(x),
+                        1 => s.push_str(&format!("+{x}")),
+                        -1 => s.push_str(&format!("-{x}")),
+                        n => s.push_str(&format!("{n:+}{x}")),
+                    }
+                }
+                let t = self.translation[i];
+                if (t - t.round()).abs() > 1e-3 {
+                    s.push_str(&fraction(t - t.floor()));
+                }
+                s
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Find space group of a periodic structure with `lattice` vectors in
+/// rows, Cartesian `positions` and atomic `numbers` of atoms. Atoms
+/// within distance `symprec` in Å are considered as overlapped.
+pub fn find_space_group(
+    lattice: [[f64; 3]; 3],
+    positions: &[[f64; 3]],
+    numbers: &[usize],
+    symprec: f64,
+) -> Result<SpaceGroup, String> {
+    if positions.is_empty() {
+        return Err("No atoms".into());
+    }
+    let lattice = DMat3::from_cols_array_2d(&lattice);
+    if lattice.determinant().abs() < 1e-3 {
+        return Err("Invalid lattice".into());
+    }
+    let positions: Vec<_> = positions.iter().map(|p| DVec3::from(*p)).collect();
+
+    // lattice translations in input cell
+    let cell = Structure::new(lattice, &positions, numbers, symprec);
+    let translations = cell.find_translations(&IDENTITY, false);
+    let shifts: Vec<_> = translations.iter().map(|(t, _)| *t).collect();
+    let primitive = primitive_lattice(lattice, &shifts)?;
+
+    // symmetry operations in primitive cell
+    let cell = Structure::new(primitive, &positions, numbers, symprec);
+    let mut operations = vec![];
+    let mut mappings: Vec<_> = translations.into_iter().map(|(_, m)| m).collect();
+    for rot in lattice_rotations(primitive, symprec) {
+        if let Some((t, mapping)) = cell.find_translations(&rot, true).pop() {
+            operations.push((rot, t));
+            mappings.push(mapping);
+        }
+    }
+    // identity first as usual
+    operations.sort_by_key(|(r, _)| *r != IDENTITY);
+    let (pg, system) = point_group(operations.iter().map(|(r, _)| r))
+        .ok_or("Symmetry operations do not form a point group; try another tolerance")?;
+    let (setting, symbol, number) = identify(primitive, &operations, pg, system, symprec)?;
+
+    // group atoms by mappings of all operations
+    let mut parent: Vec<usize> = (0..positions.len()).collect();
+    fn root(parent: &mut [usize], i: usize) -> usize {
+        let mut i = i;
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for mapping in &mappings {
+        for (i, &j) in mapping.iter().enumerate() {
+            let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+            parent[ri.max(rj)] = ri.min(rj);
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_of_root = std::collections::HashMap::new();
+    for i in 0..positions.len() {
+        let r = root(&mut parent, i);
+        let k = *group_of_root.entry(r).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[k].push(i);
+    }
+    let equivalent_atoms = groups
+        .into_iter()
+        .map(|atoms| {
+            // operations keeping the first atom
+            let f = cell.frac[atoms[0]];
+            let site: Vec<_> = operations
+                .iter()
+                .filter(|(r, t)| cell.overlap(to_dmat3(r) * f + *t, f))
+                .map(|(r, _)| *r)
+                .collect();
+            let site_symmetry = point_group(&site).map_or("?", |(pg, _)| pg);
+            let multiplicity = operations.len() * setting.centering.len() / site.len().max(1);
+            EquivalentAtoms {
+                atoms,
+                multiplicity,
+                site_symmetry,
+            }
+        })
+        .collect();
+
+    let basis = setting.basis.transpose().to_cols_array_2d();
+    Ok(SpaceGroup {
+        number,
+        symbol,
+        point_group: pg,
+        crystal_system: system,
+        operations: setting
+            .operations
+            .iter()
+            .map(|(r, t)| Operation {
+                rotation: *r,
+                translation: t.to_array(),
+            })
+            .collect(),
+        centering: setting.centering.iter().map(|c| c.to_array()).collect(),
+        conventional_lattice: basis,
+        equivalent_atoms,
+    })
+}
+// 4b8f1c6e ends here
+
+// [[file:../../../bevy.note::9c5e3a17][9c5e3a17]]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse coordinate triplet like "-y,x-y,z+1/3" as operation.
+    fn parse_operation(s: &str) -> (DMat3, DVec3) {
+        let mut rows = [[0.0; 3]; 3];
+        let mut t = [0.0; 3];
+        for (i, part) in s.split(',').enumerate() {
+            let mut sign = 1.0;
+            let mut chars = part.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '+' => sign = 1.0,
+                    '-' => sign = -1.0,
+                    'x' | 'y' | 'z' => rows[i]["xyz".find(c).unwrap()] = sign,
+                    '0'..='9' => {
+                        assert_eq!(chars.next(), Some('/'));
+                        let d = chars.next().and_then(|d| d.to_digit(10)).unwrap();
+                        t[i] += sign * c.to_digit(10).unwrap() as f64 / d as f64;
+                    }
+                    _ => panic!("invalid operation: {s}"),
+                }
+            }
+        }
+        (DMat3::from_cols_array_2d(&rows).transpose(), DVec3::from(t))
+    }
+
+    /// Return lattice vectors in rows from cell parameters.
+    fn params(a: f64, b: f64, c: f64, alpha: f64, beta: f64, gamma: f64) -> [[f64; 3]; 3] {
+        let (alpha, beta, gamma) = (alpha.to_radians(), beta.to_radians(), gamma.to_radians());
+        let cx = c * beta.cos();
+        let cy = c * (alpha.cos() - beta.cos() * gamma.cos()) / gamma.sin();
+        let cz = (c * c - cx * cx - cy * cy).sqrt();
+        [[a, 0.0, 0.0], [b * gamma.cos(), b * gamma.sin(), 0.0], [cx, cy, cz]]
+    }
+
+    /// Expand asymmetric `sites` of (atomic number, fractional
+    /// coordinates) by `operations` separated by semicolons and
+    /// `centering` translations.
+    fn expand(operations: &str, centering: &[[f64; 3]], sites: &[(usize, [f64; 3])]) -> Vec<(usize, DVec3)> {
+        let operations: Vec<_> = operations.split(';').map(parse_operation).collect();
+        let mut atoms: Vec<(usize, DVec3)> = vec![];
+        for &(n, f) in sites {
+            for (r, t) in &operations {
+                for c in centering.iter().chain([&[0.0; 3]]) {
+                    let f = wrap(*r * DVec3::from(f) + *t + DVec3::from(*c));
+                    if !atoms.iter().any(|&(m, g)| m == n && is_integral(g - f, 1e-4)) {
+                        atoms.push((n, f));
+                    }
+                }
+            }
+        }
+        atoms
+    }
+
+    fn find(lattice: [[f64; 3]; 3], atoms: &[(usize, DVec3)]) -> SpaceGroup {
+        let m = DMat3::from_cols_array_2d(&lattice);
+        let positions: Vec<_> = atoms.iter().map(|(_, f)| (m * *f).to_array()).collect();
+        let numbers: Vec<_> = atoms.iter().map(|(n, _)| *n).collect();
+        find_space_group(lattice, &positions, &numbers, 0.01).unwrap()
+    }
+
+    /// Return the multiplicities of equivalent atoms in `sg`.
+    fn multiplicities(sg: &SpaceGroup) -> Vec<usize> {
+        let mut m: Vec<_> = sg.equivalent_atoms.iter().map(|g| g.multiplicity).collect();
+        m.sort();
+        m
+    }
+
+    const F: [[f64; 3]; 3] = [[0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+    const R: [[f64; 3]; 2] = [[2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0], [1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0]];
+
+    #[test]
+    fn test_cubic() {
+        let cubic = |a| params(a, a, a, 90.0, 90.0, 90.0);
+        // Cu in primitive cell
+        let s = 3.61 / 2.0;
+        let sg = find([[0.0, s, s], [s, 0.0, s], [s, s, 0.0]], &[(29, DVec3::ZERO)]);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(225), "Fm-3m"));
+        assert_eq!(sg.centering.len(), 4);
+        assert_eq!(multiplicities(&sg), [4]);
+        assert_eq!(sg.equivalent_atoms[0].site_symmetry, "m-3m");
+
+        let atoms = expand("x,y,z", &F, &[(14, [0.0; 3]), (14, [0.25; 3])]);
+        let sg = find(cubic(5.43), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(227), "Fd-3m"));
+        assert_eq!(multiplicities(&sg), [8]);
+        assert_eq!(sg.equivalent_atoms[0].site_symmetry, "-43m");
+
+        let atoms = expand("x,y,z", &F, &[(11, [0.0; 3]), (17, [0.5, 0.0, 0.0])]);
+        let sg = find(cubic(5.64), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(225), "Fm-3m"));
+        assert_eq!(multiplicities(&sg), [4, 4]);
+        assert_eq!(sg.operations.len() * sg.centering.len(), 192);
+    }
+
+    #[test]
+    fn test_rutile() {
+        let u = 0.305;
+        let frac = [
+            [0.0; 3],
+            [0.5; 3],
+            [u, u, 0.0],
+            [-u, -u, 0.0],
+            [0.5 + u, 0.5 - u, 0.5],
+            [0.5 - u, 0.5 + u, 0.5],
+        ];
+        let atoms: Vec<_> = [22, 22, 8, 8, 8, 8].into_iter().zip(frac.map(DVec3::from)).collect();
+        let sg = find(params(4.594, 4.594, 2.959, 90.0, 90.0, 90.0), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(136), "P4_2/mnm"));
+        assert_eq!(multiplicities(&sg), [2, 4]);
+    }
+
+    #[test]
+    fn test_hexagonal() {
+        let hexagonal = |a, c| params(a, a, c, 90.0, 90.0, 120.0);
+        let u = 0.382;
+        let frac = [
+            [1.0 / 3.0, 2.0 / 3.0, 0.0],
+            [2.0 / 3.0, 1.0 / 3.0, 0.5],
+            [1.0 / 3.0, 2.0 / 3.0, u],
+            [2.0 / 3.0, 1.0 / 3.0, 0.5 + u],
+        ];
+        let atoms: Vec<_> = [30, 30, 8, 8].into_iter().zip(frac.map(DVec3::from)).collect();
+        let sg = find(hexagonal(3.25, 5.2), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(186), "P6_3mc"));
+        assert_eq!(multiplicities(&sg), [2, 2]);
+
+        // α-quartz in both enantiomorphs
+        let p3121 = "x,y,z;-y,x-y,z+1/3;-x+y,-x,z+2/3;y,x,-z;x-y,-y,-z+2/3;-x,-x+y,-z+1/3";
+        let atoms = expand(p3121, &[], &[(14, [0.47, 0.0, 1.0 / 3.0]), (8, [0.413, 0.267, 0.214])]);
+        let sg = find(hexagonal(4.91, 5.4), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(152), "P3_121"));
+        assert_eq!(multiplicities(&sg), [3, 6]);
+        let p3221 = "x,y,z;-y,x-y,z+2/3;-x+y,-x,z+1/3;y,x,-z;x-y,-y,-z+1/3;-x,-x+y,-z+2/3";
+        let atoms = expand(p3221, &[], &[(14, [0.47, 0.0, 2.0 / 3.0]), (8, [0.413, 0.267, 0.214])]);
+        let sg = find(hexagonal(4.91, 5.4), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(154), "P3_221"));
+        assert_eq!(multiplicities(&sg), [3, 6]);
+
+        // corundum in hexagonal setting
+        let r3c = "x,y,z;-y,x-y,z;-x+y,-x,z;y,x,-z+1/2;x-y,-y,-z+1/2;-x,-x+y,-z+1/2;\
+                   -x,-y,-z;y,-x+y,-z;x-y,x,-z;-y,-x,z+1/2;-x+y,y,z+1/2;x,x-y,z+1/2";
+        let atoms = expand(r3c, &R, &[(13, [0.0, 0.0, 0.352]), (8, [0.306, 0.0, 0.25])]);
+        let sg = find(hexagonal(4.76, 12.99), &atoms);
+        assert_eq!((sg.number, sg.symbol.as_str()), (Some(167), "R-3c"));
+        assert_eq!(sg.centering.len(), 3);
+        assert_eq!(multiplicities(&sg), [12, 18]);
+    }
+
+    #[test]
+    fn test_monoclinic() {
+        let c2c = "x,y,z;-x,y,-z+1/2;-x,-y,-z;x,-y,z+1/2";
+        let atoms = expand(c2c, &[F[2]], &[(6, [0.11, 0.23, 0.37]), (8, [0.0, 0.31, 0.25])]);
+        let lattice = params(9.1, 6.3, 7.7, 90.0, 110.0, 90.0);
+        // the same structure in I centered cell: a' = -a - c, c' = a
+        let m = DMat3::from_cols_array_2d(&lattice);
+        let t = DMat3::from_cols_array_2d(&[[-1.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]);
+        let lattice_i = (m * t).to_cols_array_2d();
+        let atoms_i: Vec<_> = atoms.iter().map(|&(n, f)| (n, wrap(t.inverse() * f))).collect();
+
+        for sg in [find(lattice, &atoms), find(lattice_i, &atoms_i)] {
+            assert_eq!((sg.number, sg.symbol.as_str()), (Some(15), "C2/c"));
+            assert_eq!(multiplicities(&sg), [4, 8]);
+            // the operations are given in the cell of the symbol
+            assert!(sg
+                .centering
+                .iter()
+                .any(|c| is_integral(DVec3::from(*c) - DVec3::new(0.5, 0.5, 0.0), 1e-6)));
+            let glide = sg
+                .operations
+                .iter()
+                .find(|op| op.rotation == [[1, 0, 0], [0, -1, 0], [0, 0, 1]])
+                .expect("glide plane normal to b");
+            assert!(sg.centering.iter().any(|c| {
+                let t = wrap(DVec3::from(glide.translation) + DVec3::from(*c));
+                is_integral(DVec3::new(t.x, 0.0, t.z - 0.5), 1e-6)
+            }));
+        }
+    }
+}
+// 9c5e3a17 ends here
@@ -0,0 +1,266 @@
+// [[file:../../bevy.note::2c7e5b91][2c7e5b91]]
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui::Ui;
+use gchemol::{Lattice, Molecule};
+
+use crate::base::CurrentFrame;
+use crate::molecule::MoleculeTrajectory;
+// 2c7e5b91 ends here
+
+// [[file:../../bevy.note::8f4d0a6e][8f4d0a6e]]
+/// Return the new lattice with parameters `params` (a, b, c in Å, and
+/// α, β, γ in degrees) for `lat`. The orientation of `lat` is kept if
+/// only lengths changed.
+fn lattice_from_params(lat: &Lattice, params: [f64; 6]) -> Lattice {
+    let [a, b, c, alpha, beta, gamma] = params;
+    let angles = lat.angles();
+    let same_angles = angles.iter().zip([alpha, beta, gamma]).all(|(x, y)| (x - y).abs() < 1e-6);
+    let mut new_lat = if same_angles {
+        let vectors = lat.vectors();
+        let lengths = lat.lengths();
+        let scaled: Vec<[f64; 3]> = vectors
+            .iter()
+            .zip(lengths.iter().zip([a, b, c]))
+            .map(|(v, (l0, l))| (v * (l / l0)).into())
+            .collect();
+        Lattice::new([scaled[0], scaled[1], scaled[2]])
+    } else {
+        Lattice::from_params(a, b, c, alpha, beta, gamma)
+    };
+    new_lat.set_origin(lat.origin());
+    new_lat
+}
+
+/// Return error if `lat` is not a valid unit cell.
+fn check_lattice(lat: &Lattice) -> Result<(), String> {
+    let volume = lat.volume().abs();
+    if volume.is_finite() && volume > 1e-3 {
+        Ok(())
+    } else {
+        Err("Invalid lattice with zero volume".into())
+    }
+}
+
+/// Set lattice of `mol` to `lat`. Atoms are moved with the cell in
+/// fractional coordinates if `scale_atoms` is true, otherwise their
+/// Cartesian coordinates are kept.
+pub fn set_lattice(mol: &mut Molecule, lat: Lattice, scale_atoms: bool) -> Result<(), String> {
+    check_lattice(&lat)?;
+    if scale_atoms && mol.lattice.is_some() {
+        mol.set_lattice_scaled(lat);
+    } else {
+        mol.set_lattice(lat);
+    }
+    Ok(())
+}
+
+/// Add an orthorhombic lattice to `mol` by boxing it with `vacuum`
+/// padding in Å on each side. Atoms are translated into the box.
+pub fn add_lattice_with_vacuum(mol: &mut Molecule, vacuum: f64) -> Result<(), String> {
+    let positions: Vec<[f64; 3]> = mol.positions().collect();
+    if positions.is_empty() {
+        return Err("No atoms to box".into());
+    }
+    let mut lower = [f64::MAX; 3];
+    let mut upper = [f64::MIN; 3];
+    for p in &positions {
+        for k in 0..3 {
+            lower[k] = lower[k].min(p[k]);
+            upper[k] = upper[k].max(p[k]);
+        }
+    }
+    let lengths: Vec<_> = (0..3).map(|k| upper[k] - lower[k] + 2.0 * vacuum).collect();
+    let lat = Lattice::new([[lengths[0], 0.0, 0.0], [0.0, lengths[1], 0.0], [0.0, 0.0, lengths[2]]]);
+    check_lattice(&lat)?;
+    let shifted = positions.iter().map(|p| [0, 1, 2].map(|k| p[k] - lower[k] + vacuum));
+    mol.set_positions(shifted);
+    mol.set_lattice(lat);
+    Ok(())
+}
+// 8f4d0a6e ends here
+
+// [[file:../../bevy.note::d61b2f47][d61b2f47]]
+#[derive(Debug, Resource)]
+pub struct State {
+    /// Lattice parameters: a, b, c, α, β, γ
+    params: [f64; 6],
+    /// Lattice vectors a, b, c in rows
+    vectors: [[f64; 3]; 3],
+    /// Keep fractional coordinates on changing lattice
+    scale_atoms: bool,
+    /// Vacuum padding for boxing non-periodic molecule
+    vacuum: f64,
+    /// Editing values have been taken from current molecule
+    loaded: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            params: [10.0, 10.0, 10.0, 90.0, 90.0, 90.0],
+            vectors: [[10.0, 0.0, 0.0], [0.0, 10.0, 0.0], [0.0, 0.0, 10.0]],
+            scale_atoms: true,
+            vacuum: 10.0,
+            loaded: false,
+        }
+    }
+}
+
+/// Edits of unit cell
+enum Action {
+    /// Apply lattice parameters
+    Parameters,
+    /// Apply lattice vectors
+    Vectors,
+    /// Add lattice by boxing molecule
+    AddLattice,
+}
+
+impl State {
+    /// Take editing values from lattice of `mol`.
+    fn load(&mut self, mol: &Molecule) {
+        if let Some(lat) = mol.get_lattice() {
+            let [a, b, c] = lat.lengths();
+            let [alpha, beta, gamma] = lat.angles();
+            self.params = [a, b, c, alpha, beta, gamma];
+            self.vectors = lat.vectors().map(|v| v.into());
+        }
+        self.loaded = true;
+    }
+
+    fn show(&mut self, ui: &mut Ui, mol: &Molecule) -> Option<Action> {
+        let mut action = None;
+        let Some(lat) = mol.get_lattice() else {
+            ui.label("No lattice in current molecule.");
+            ui.horizontal(|ui| {
+                ui.label("Vacuum padding");
+                ui.add(
+                    egui::DragValue::new(&mut self.vacuum)
+                        .speed(0.1)
+                        .clamp_range(0.0..=100.0)
+                        .suffix(" Å"),
+                );
+            });
+            if ui
+                .button("Add lattice")
+                .on_hover_text("Box the molecule with vacuum padding on each side")
+                .clicked()
+            {
+                action = Some(Action::AddLattice);
+            }
+            return action;
+        };
+
+        ui.heading("Lattice parameters");
+        egui::Grid::new("unit_cell_params_grid").num_columns(4).show(ui, |ui| {
+            let (lengths, angles) = self.params.split_at_mut(3);
+            for (k, (l, a)) in lengths.iter_mut().zip(angles.iter_mut()).enumerate() {
+                ui.label(["a", "b", "c"][k]);
+                ui.add(egui::DragValue::new(l).speed(0.01).clamp_range(0.1..=1000.0).suffix(" Å"));
+                ui.label(["α", "β", "γ"][k]);
+                ui.add(egui::DragValue::new(a).speed(0.1).clamp_range(1.0..=179.0).suffix("°"));
+                ui.end_row();
+            }
+        });
+        if ui.button("Apply parameters").clicked() {
+            action = Some(Action::Parameters);
+        }
+        ui.separator();
+
+        ui.heading("Lattice vectors");
+        egui::Grid::new("unit_cell_vectors_grid").num_columns(4).show(ui, |ui| {
+            for (k, v) in self.vectors.iter_mut().enumerate() {
+                ui.label(["a", "b", "c"][k]);
+                for x in v.iter_mut() {
+                    ui.add(egui::DragValue::new(x).speed(0.01).max_decimals(6));
+                }
+                ui.end_row();
+            }
+        });
+        if ui.button("Apply vectors").clicked() {
+            action = Some(Action::Vectors);
+        }
+        ui.separator();
+
+        ui.label(format!("Volume: {:.4} Å³", lat.volume().abs()));
+        ui.checkbox(&mut self.scale_atoms, "Scale atoms with cell")
+            .on_hover_text("Keep fractional coordinates of atoms, otherwise keep Cartesian coordinates");
+        if ui
+            .button("Reset")
+            .on_hover_text("Reset values from current lattice")
+            .clicked()
+        {
+            self.load(mol);
+        }
+        action
+    }
+}
+// d61b2f47 ends here
+
+// [[file:../../bevy.note::5b3a9e0c][5b3a9e0c]]
+/// Edit unit cell of current molecule.
+pub fn unit_cell_window_system(
+    mut ui_state: ResMut<super::UiState>,
+    mut state: ResMut<State>,
+    mut contexts: EguiContexts,
+    mut traj: ResMut<MoleculeTrajectory>,
+    current_frame: Res<CurrentFrame>,
+    mut history: ResMut<super::history::History>,
+) {
+    if !ui_state.unit_cell_window_open {
+        // reload values on next opening
+        if state.loaded {
+            state.loaded = false;
+        }
+        return;
+    }
+    let Some(mol) = traj.get_current_molecule(&current_frame) else {
+        return;
+    };
+    if !state.loaded || traj.is_changed() || current_frame.is_changed() {
+        state.load(mol);
+    }
+
+    let ctx = contexts.ctx_mut();
+    let mut action = None;
+    egui::Window::new("Unit cell")
+        .open(&mut ui_state.unit_cell_window_open)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            action = state.show(ui, mol);
+        });
+
+    let Some(action) = action else {
+        return;
+    };
+    let description = match action {
+        Action::AddLattice => "Add lattice",
+        _ => "Edit unit cell",
+    };
+    // edit a copy, so that nothing is recorded for undo on failure
+    let mut new_mol = mol.clone();
+    let result = match (action, mol.get_lattice()) {
+        (Action::Parameters, Some(lat)) => {
+            let lat = lattice_from_params(lat, state.params);
+            set_lattice(&mut new_mol, lat, state.scale_atoms)
+        }
+        (Action::Vectors, Some(lat)) => {
+            let mut new_lat = Lattice::new(state.vectors);
+            new_lat.set_origin(lat.origin());
+            set_lattice(&mut new_mol, new_lat, state.scale_atoms)
+        }
+        (Action::AddLattice, None) => add_lattice_with_vacuum(&mut new_mol, state.vacuum),
+        _ => Err("No lattice to edit".into()),
+    };
+    if let Err(err) = result {
+        ui_state.message = err;
+        return;
+    }
+    history.record_current_frame(description, &traj, &current_frame);
+    if let Some(mol) = traj.get_current_molecule_mut(&current_frame) {
+        *mol = new_mol;
+    }
+    ui_state.message = format!("{description}: done");
+}
+// 5b3a9e0c ends here
@@ -0,0 +1,149 @@
+// [[file:../../bevy.note::a4f0c7e2][a4f0c7e2]]
+use bevy::math::DVec3;
+use bevy_egui::egui::Ui;
+use gchemol::Molecule;
+
+use super::history::History;
+use crate::base::CurrentFrame;
+use crate::molecule::MoleculeTrajectory;
+// a4f0c7e2 ends here
+
+// [[file:../../bevy.note::3e91d5b6][3e91d5b6]]
+/// Return the positions of bonded fragments in `mol` made whole
+/// following the minimum image convention, i.e. bonds are no longer
+/// broken by cell boundaries.
+fn whole_fragments(mol: &Molecule) -> Vec<Vec<(usize, DVec3)>> {
+    use std::collections::HashSet;
+
+    let lat = mol.get_lattice().expect("periodic structure");
+    // bonds across cell boundaries are required
+    let mut periodic = mol.clone();
+    periodic.rebond();
+
+    let position = |i: usize| DVec3::from(periodic.get_atom_unchecked(i).position());
+    let mut visited = HashSet::new();
+    let mut fragments = vec![];
+    for i0 in periodic.numbers() {
+        if !visited.insert(i0) {
+            continue;
+        }
+        let mut fragment = vec![(i0, position(i0))];
+        let mut k = 0;
+        while k < fragment.len() {
+            let (i, pi) = fragment[k];
+            for j in periodic.connected(i) {
+                if visited.insert(j) {
+                    let d = position(j) - position(i);
+                    let d = DVec3::from(<[f64; 3]>::from(lat.apply_mic(d.to_array())));
+                    fragment.push((j, pi + d));
+                }
+            }
+            k += 1;
+        }
+        fragments.push(fragment);
+    }
+    fragments
+}
+
+/// Wrap atoms of `mol` into unit cell with fractional coordinates in
+/// [0, 1). If `keep_whole` is true, the centroids of bonded fragments
+/// are wrapped instead of single atoms, which keeps connectivity
+/// unchanged. Otherwise bonds of the moved atoms are recomputed
+/// afterwards.
+pub fn wrap_atoms(mol: &mut Molecule, keep_whole: bool) -> Result<(), String> {
+    let lat = *mol.get_lattice().ok_or("No lattice in current molecule")?;
+    let new_positions: Vec<(usize, DVec3)> = if keep_whole {
+        let origin = DVec3::from(<[f64; 3]>::from(lat.to_cart([0.0; 3])));
+        whole_fragments(mol)
+            .into_iter()
+            .flat_map(|fragment| {
+                let centroid = fragment.iter().map(|(_, p)| *p).sum::<DVec3>() / fragment.len() as f64;
+                let f = DVec3::from(<[f64; 3]>::from(lat.to_frac(centroid.to_array())));
+                let shift = DVec3::from(<[f64; 3]>::from(lat.to_cart(f.floor().to_array()))) - origin;
+                fragment.into_iter().map(move |(i, p)| (i, p - shift))
+            })
+            .collect()
+    } else {
+        mol.atoms()
+            .map(|(i, a)| (i, DVec3::from(<[f64; 3]>::from(lat.wrap(a.position())))))
+            .collect()
+    };
+    let mut moved = vec![];
+    for (i, p) in new_positions {
+        if DVec3::from(mol.get_atom_unchecked(i).position()) != p {
+            mol.set_position(i, p.to_array());
+            moved.push(i);
+        }
+    }
+    if mol.nbonds() == 0 {
+        // create bonds without periodic images as on loading
+        let lat = mol.unbuild_crystal();
+        mol.rebond();
+        mol.lattice = lat;
+    } else if !keep_whole {
+        super::move_atoms::rebond_atoms(mol, &moved);
+    }
+    Ok(())
+}
+// 3e91d5b6 ends here
+
+// [[file:../../bevy.note::0b7d2ec4][0b7d2ec4]]
+/// Show menu items for wrapping atoms. Return true for wrapping all
+/// frames, or false for current frame only.
+pub fn show_menu(ui: &mut Ui, keep_whole: &mut bool) -> Option<bool> {
+    let mut all_frames = None;
+    ui.checkbox(keep_whole, "Keep molecules whole")
+        .on_hover_text("Wrap centroids of bonded fragments instead of single atoms");
+    if ui.button("Current frame").clicked() {
+        all_frames = Some(false);
+    }
+    if ui.button("All frames").clicked() {
+        all_frames = Some(true);
+    }
+    if all_frames.is_some() {
+        ui.close_menu();
+    }
+    all_frames
+}
+
+/// Wrap atoms in current frame or `all_frames` of `traj` with
+/// recording for undo. Return the message for display.
+pub fn wrap_trajectory(
+    traj: &mut MoleculeTrajectory,
+    current_frame: &CurrentFrame,
+    history: &mut History,
+    all_frames: bool,
+    keep_whole: bool,
+) -> String {
+    let description = "Wrap atoms to unit cell";
+    if all_frames {
+        if traj.get_molecules().iter().any(|mol| mol.lattice.is_none()) {
+            return "Not all frames have lattice".into();
+        }
+        let mut mols = traj.get_molecules().to_vec();
+        for mol in mols.iter_mut() {
+            if let Err(err) = wrap_atoms(mol, keep_whole) {
+                return err;
+            }
+        }
+        history.record_trajectory(description, traj);
+        traj.replace_frames(mols);
+        format!("Atoms wrapped in {} frames", traj.nframes())
+    } else {
+        if traj
+            .get_current_molecule(current_frame)
+            .and_then(|mol| mol.get_lattice())
+            .is_none()
+        {
+            return "No lattice in current molecule".into();
+        }
+        history.record_current_frame(description, traj, current_frame);
+        if let Some(mol) = traj.get_current_molecule_mut(current_frame) {
+            if let Err(err) = wrap_atoms(mol, keep_whole) {
+                return err;
+            }
+        }
+        "Atoms wrapped in current frame".into()
+    }
+}
+// 0b7d2ec4 ends here